@@ -0,0 +1,29 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+import java.util.List;
+import java.util.Map;
+
+/**
+ * The response of {@link RustJavaDemo#fetch(String)}. It's created by the Rust
+ * side through the canonical constructor.
+ *
+ * @param status  the HTTP status code
+ * @param headers the response headers, a header can have multiple values
+ * @param body    the response body
+ */
+public record HttpResponse(int status, Map<String, List<String>> headers, byte[] body) {
+}
@@ -0,0 +1,82 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+import java.lang.ref.Cleaner;
+import java.nio.ByteBuffer;
+
+/**
+ * The bytes that are owned by the demo Rust lib, exposed as a direct
+ * {@link ByteBuffer} without being copied to the Java heap.
+ *
+ * The memory is released when the buffer is closed, it's recommended to use it
+ * in a try-with-resources block. If it's never closed, the memory is released
+ * when neither this buffer nor any of the {@link #buffer()}s is reachable, as
+ * a last resort.
+ * Accessing the {@link #buffer()} after closing is undefined behavior, so it
+ * must not escape the buffer's lifetime if it's closed.
+ */
+public class NativeBuffer implements AutoCloseable {
+
+    private static final Cleaner CLEANER = Cleaner.create();
+
+    private final ByteBuffer buffer;
+    private final Cleaner.Cleanable cleanable;
+
+    private NativeBuffer(ByteBuffer buffer, long handle) {
+        this.buffer = buffer;
+        // Registered on the original direct buffer rather than `this`: the views of it that are
+        // handed out keep it reachable, even after this wrapper is dropped.
+        this.cleanable = CLEANER.register(buffer, new Releaser(handle));
+    }
+
+    // Used internally by the Rust side.
+    static NativeBuffer wrap(ByteBuffer buffer, long handle) {
+        return new NativeBuffer(buffer, handle);
+    }
+
+    /**
+     * The bytes, as a read-only direct buffer.
+     */
+    public ByteBuffer buffer() {
+        return this.buffer.asReadOnlyBuffer();
+    }
+
+    /**
+     * Release the memory in Rust. It's idempotent.
+     */
+    @Override
+    public void close() {
+        this.cleanable.clean();
+    }
+
+    // Must not refer to the `NativeBuffer` or the `ByteBuffer`, or it would never be phantom
+    // reachable.
+    private static class Releaser implements Runnable {
+
+        private final long handle;
+
+        Releaser(long handle) {
+            this.handle = handle;
+        }
+
+        @Override
+        public void run() {
+            free(this.handle);
+        }
+    }
+
+    private static native void free(long handle);
+}
@@ -0,0 +1,166 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+/**
+ * The configuration of the tokio runtime in the demo Rust lib. It's read by
+ * the Rust side in {@link RustJavaDemo#libInit(RuntimeConfig)}, and validated
+ * there as well. The Rust side reads the fields directly, so they must be kept
+ * in sync with the bindings in Rust when renamed.
+ *
+ * For all the numeric options, leaving them to 0 will use the tokio's
+ * defaults.
+ */
+public class RuntimeConfig {
+
+    private int workerThreads;
+    private int maxBlockingThreads;
+    private long threadStackSize;
+    private long threadKeepAliveMillis;
+    private String threadNamePrefix;
+    private int eventInterval;
+    private int globalQueueInterval;
+    private boolean currentThread;
+
+    public static Builder newBuilder() {
+        return new Builder();
+    }
+
+    /**
+     * The number of the worker threads, 0 means the cpu's cores.
+     */
+    public int getWorkerThreads() {
+        return workerThreads;
+    }
+
+    /**
+     * The max number of the threads in the blocking pool.
+     */
+    public int getMaxBlockingThreads() {
+        return maxBlockingThreads;
+    }
+
+    /**
+     * The stack size (in bytes) of the runtime's threads.
+     */
+    public long getThreadStackSize() {
+        return threadStackSize;
+    }
+
+    /**
+     * How long the idle threads in the blocking pool are kept alive.
+     */
+    public long getThreadKeepAliveMillis() {
+        return threadKeepAliveMillis;
+    }
+
+    /**
+     * The runtime's threads are named as "{prefix}-{n}", and are attached to
+     * the JVM under a `ThreadGroup` named "{prefix}", so that they are easy to
+     * tell from the others in the thread dumps. A `null` prefix, the default,
+     * means "greptime-rust-{name}" of the runtime's name, e.g.
+     * "greptime-rust-default" for the one created in `libInit`.
+     */
+    public String getThreadNamePrefix() {
+        return threadNamePrefix;
+    }
+
+    /**
+     * The number of scheduler ticks after which the scheduler will poll for
+     * external events (timers, I/O, and so on).
+     */
+    public int getEventInterval() {
+        return eventInterval;
+    }
+
+    /**
+     * The number of scheduler ticks after which the scheduler will poll the
+     * global task queue.
+     */
+    public int getGlobalQueueInterval() {
+        return globalQueueInterval;
+    }
+
+    /**
+     * Whether to run all the tasks in a single thread. Useful for
+     * deterministic tests. The `workerThreads` must not be set in this mode.
+     */
+    public boolean isCurrentThread() {
+        return currentThread;
+    }
+
+    @Override
+    public String toString() {
+        return "RuntimeConfig{" +
+                "workerThreads=" + workerThreads +
+                ", maxBlockingThreads=" + maxBlockingThreads +
+                ", threadStackSize=" + threadStackSize +
+                ", threadKeepAliveMillis=" + threadKeepAliveMillis +
+                ", threadNamePrefix='" + threadNamePrefix + '\'' +
+                ", eventInterval=" + eventInterval +
+                ", globalQueueInterval=" + globalQueueInterval +
+                ", currentThread=" + currentThread +
+                '}';
+    }
+
+    public static class Builder {
+
+        private final RuntimeConfig config = new RuntimeConfig();
+
+        public Builder workerThreads(int workerThreads) {
+            this.config.workerThreads = workerThreads;
+            return this;
+        }
+
+        public Builder maxBlockingThreads(int maxBlockingThreads) {
+            this.config.maxBlockingThreads = maxBlockingThreads;
+            return this;
+        }
+
+        public Builder threadStackSize(long threadStackSize) {
+            this.config.threadStackSize = threadStackSize;
+            return this;
+        }
+
+        public Builder threadKeepAliveMillis(long threadKeepAliveMillis) {
+            this.config.threadKeepAliveMillis = threadKeepAliveMillis;
+            return this;
+        }
+
+        public Builder threadNamePrefix(String threadNamePrefix) {
+            this.config.threadNamePrefix = threadNamePrefix;
+            return this;
+        }
+
+        public Builder eventInterval(int eventInterval) {
+            this.config.eventInterval = eventInterval;
+            return this;
+        }
+
+        public Builder globalQueueInterval(int globalQueueInterval) {
+            this.config.globalQueueInterval = globalQueueInterval;
+            return this;
+        }
+
+        public Builder currentThread(boolean currentThread) {
+            this.config.currentThread = currentThread;
+            return this;
+        }
+
+        public RuntimeConfig build() {
+            return this.config;
+        }
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+import java.util.Arrays;
+
+/**
+ * A snapshot of the tokio runtime metrics in the demo Rust lib. It's created
+ * by the Rust side in {@link RustJavaDemo#runtimeMetrics(String)}.
+ *
+ * The per worker metrics are arrays indexed by the worker's index.
+ */
+public class RuntimeMetrics {
+
+    private final int workers;
+    private final long aliveTasks;
+    private final long globalQueueDepth;
+    private final long[] workerLocalQueueDepths;
+    private final long[] workerBusyDurationNanos;
+    private final long[] workerParkCounts;
+    private final int blockingThreads;
+    private final int idleBlockingThreads;
+    private final long blockingQueueDepth;
+
+    // Used internally by the Rust side.
+    RuntimeMetrics(int workers,
+            long aliveTasks,
+            long globalQueueDepth,
+            long[] workerLocalQueueDepths,
+            long[] workerBusyDurationNanos,
+            long[] workerParkCounts,
+            int blockingThreads,
+            int idleBlockingThreads,
+            long blockingQueueDepth) {
+        this.workers = workers;
+        this.aliveTasks = aliveTasks;
+        this.globalQueueDepth = globalQueueDepth;
+        this.workerLocalQueueDepths = workerLocalQueueDepths;
+        this.workerBusyDurationNanos = workerBusyDurationNanos;
+        this.workerParkCounts = workerParkCounts;
+        this.blockingThreads = blockingThreads;
+        this.idleBlockingThreads = idleBlockingThreads;
+        this.blockingQueueDepth = blockingQueueDepth;
+    }
+
+    /**
+     * The number of the worker threads.
+     */
+    public int getWorkers() {
+        return workers;
+    }
+
+    /**
+     * The number of the tasks that are alive (spawned but not finished).
+     */
+    public long getAliveTasks() {
+        return aliveTasks;
+    }
+
+    /**
+     * The number of the tasks that are waiting in the global queue.
+     */
+    public long getGlobalQueueDepth() {
+        return globalQueueDepth;
+    }
+
+    /**
+     * The number of the tasks that are waiting in each worker's local queue.
+     */
+    public long[] getWorkerLocalQueueDepths() {
+        return workerLocalQueueDepths;
+    }
+
+    /**
+     * The total time (in nanoseconds) that each worker has been busy.
+     */
+    public long[] getWorkerBusyDurationNanos() {
+        return workerBusyDurationNanos;
+    }
+
+    /**
+     * The number of times that each worker has parked.
+     */
+    public long[] getWorkerParkCounts() {
+        return workerParkCounts;
+    }
+
+    /**
+     * The number of the threads in the blocking pool, including the idle ones.
+     */
+    public int getBlockingThreads() {
+        return blockingThreads;
+    }
+
+    /**
+     * The number of the idle threads in the blocking pool.
+     */
+    public int getIdleBlockingThreads() {
+        return idleBlockingThreads;
+    }
+
+    /**
+     * The number of the tasks that are waiting for a thread in the blocking
+     * pool.
+     */
+    public long getBlockingQueueDepth() {
+        return blockingQueueDepth;
+    }
+
+    @Override
+    public String toString() {
+        return "RuntimeMetrics{" +
+                "workers=" + workers +
+                ", aliveTasks=" + aliveTasks +
+                ", globalQueueDepth=" + globalQueueDepth +
+                ", workerLocalQueueDepths=" + Arrays.toString(workerLocalQueueDepths) +
+                ", workerBusyDurationNanos=" + Arrays.toString(workerBusyDurationNanos) +
+                ", workerParkCounts=" + Arrays.toString(workerParkCounts) +
+                ", blockingThreads=" + blockingThreads +
+                ", idleBlockingThreads=" + idleBlockingThreads +
+                ", blockingQueueDepth=" + blockingQueueDepth +
+                '}';
+    }
+}
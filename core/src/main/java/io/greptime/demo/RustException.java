@@ -0,0 +1,45 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+/**
+ * Thrown when an operation in the demo Rust lib fails. The subclasses tell the
+ * kinds of the failures apart, e.g. to decide whether to retry:
+ * <ul>
+ * <li>{@link RustJniException}, the calls between Rust and Java failed.</li>
+ * <li>{@link RustHttpException}, the HTTP request failed.</li>
+ * <li>{@link RustTimeoutException}, the operation is not done before its
+ * deadline.</li>
+ * </ul>
+ * The others, like an invalid argument or a runtime that is shut down, are
+ * thrown as a plain {@link RustException}.
+ */
+public class RustException extends RuntimeException {
+
+    private final String location;
+
+    public RustException(String message, String location) {
+        super(message);
+        this.location = location;
+    }
+
+    /**
+     * The location in the Rust source where the error is raised, as
+     * "file:line:column".
+     */
+    public String getLocation() {
+        return this.location;
+    }
+}
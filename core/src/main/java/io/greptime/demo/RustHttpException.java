@@ -0,0 +1,47 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+/**
+ * Thrown when an HTTP request in Rust fails, like the connection is refused,
+ * the response has an error status (4xx or 5xx), or its body is broken.
+ */
+public class RustHttpException extends RustException {
+
+    private final int status;
+    private final String url;
+
+    public RustHttpException(String message, String location, int status, String url) {
+        super(message, location);
+        this.status = status;
+        this.url = url;
+    }
+
+    /**
+     * The status code of the response, or 0 if the request failed before a
+     * response is received.
+     */
+    public int getStatus() {
+        return this.status;
+    }
+
+    /**
+     * The URL of the request, or `null` if it's not known, e.g. the URL is
+     * invalid.
+     */
+    public String getUrl() {
+        return this.url;
+    }
+}
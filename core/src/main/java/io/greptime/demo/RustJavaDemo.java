@@ -14,21 +14,34 @@
 
 package io.greptime.demo;
 
-import io.greptime.demo.utils.AsyncRegistry;
 import io.greptime.demo.utils.Logger;
+import io.greptime.demo.utils.RustPublisher;
 import io.questdb.jar.jni.JarJniLoader;
+import java.time.Duration;
+import java.util.HashMap;
+import java.util.Map;
 import java.util.concurrent.CompletableFuture;
+import java.util.concurrent.Flow;
+import org.apache.arrow.c.ArrowArrayStream;
+import org.apache.arrow.c.Data;
+import org.apache.arrow.memory.BufferAllocator;
+import org.apache.arrow.vector.ipc.ArrowReader;
 
 /**
  * The main functionality of this class is implemented in Rust. Called from Java
  * via JNI. So it's important to load the Rust lib first. It's recommended to
- * call {@link #libInit()} like this:
+ * call {@link #libInit(int)} or {@link #libInit(RuntimeConfig)} like this:
  * 
  * <pre>
  * class YourMainClass {
  *     static {
  *         // Init the demo Rust lib, and create a runtime of size 8.
  *         RustJavaDemo.libInit(8);
+ *         // Or with a more detailed config:
+ *         // RustJavaDemo.libInit(RuntimeConfig.newBuilder()
+ *         //         .workerThreads(8)
+ *         //         .maxBlockingThreads(64)
+ *         //         .build());
  *     }
  *
  *     public static void main(String[] args) {
@@ -42,27 +55,263 @@ public class RustJavaDemo {
     private static final Logger LOGGER = Logger.getLogger(RustJavaDemo.class);
 
     static {
-        JarJniLoader.loadLib(
-                RustJavaDemo.class,
-                "/io/greptime/demo/rust/libs",
-                "demo");
+        // The native methods are registered to the classes in the package of this
+        // class when the lib is loaded, so they still work if the package is
+        // relocated, e.g. by shading. The property is only set while loading the
+        // lib, under a lock that is shared by all the (shaded) copies of this class
+        // (the interned key), so they don't read the names of each other.
+        String key = "greptime.demo.native.class";
+        synchronized (key.intern()) {
+            System.setProperty(key, RustJavaDemo.class.getName());
+            try {
+                JarJniLoader.loadLib(
+                        RustJavaDemo.class,
+                        "/io/greptime/demo/rust/libs",
+                        "demo");
+            } finally {
+                System.clearProperty(key);
+            }
+        }
     }
 
     /**
      * Load the demo rust lib and init it with a size of it's runtime. Setting
      * the `runtimeSize` to 0 will use the cpu's cores.
      * This method can be called multiple times, only the first call will take
-     * effect.
+     * effect, until the lib is shut down by {@link #libShutdown(long)}.
      */
-    public static native void libInit(int runtimeSize);
+    public static void libInit(int runtimeSize) {
+        libInit(RuntimeConfig.newBuilder().workerThreads(runtimeSize).build());
+    }
+
+    /**
+     * Load the demo rust lib and init its runtime with the `config`. A `null`
+     * config will use the default one. An invalid config results in a
+     * {@link RustException}.
+     * Like {@link #libInit(int)}, only the first call will take effect.
+     */
+    public static native void libInit(RuntimeConfig config);
+
+    /**
+     * Register an extra runtime of the `name`, which has its own threads, so
+     * that the tasks in it can't starve the others. Must be called after the
+     * lib is initialized, and a runtime name can only be registered once.
+     * The runtime created in {@link #libInit(RuntimeConfig)} is named
+     * "default". A `null` config will use the default one.
+     */
+    public static native void registerRuntime(String name, RuntimeConfig config);
+
+    /**
+     * Take a snapshot of the metrics of the default runtime.
+     */
+    public static RuntimeMetrics runtimeMetrics() {
+        return runtimeMetrics(null);
+    }
+
+    /**
+     * Take a snapshot of the metrics of the runtime of the name `runtime`, a
+     * `null` runtime means the default one.
+     */
+    public static native RuntimeMetrics runtimeMetrics(String runtime);
+
+    /**
+     * Shutdown all the runtimes of the demo rust lib. It stops accepting new tasks,
+     * and waits up to `timeoutMillis` for the in-flight tasks to be done. The
+     * futures of the tasks that are not done by then are completed
+     * exceptionally with a "runtime shut down" error, and so are the streams
+     * of the publishers that are not done. The references to the Java classes
+     * that are held in Rust are released as well, so the class loader of this
+     * class can be garbage collected (and the native lib unloaded), e.g. when
+     * the app is redeployed in an app server.
+     * After the shutdown, the lib can be initialized again by {@link #libInit(int)}.
+     * It can't be called from the threads of the runtimes, e.g. in a callback
+     * of a future that is completed by the lib, a {@link RustException} is
+     * thrown then.
+     */
+    public static native void libShutdown(long timeoutMillis);
 
     /**
      * Hello to fetch the web content of the param `url`.
      */
     public CompletableFuture<String> hello(String url) {
-        long futureId = nativeHello(url);
-        return AsyncRegistry.take(futureId);
+        return hello(url, null);
+    }
+
+    /**
+     * Hello to fetch the web content of the param `url`, in the runtime of the
+     * name `runtime`, a `null` runtime means the default one.
+     */
+    public CompletableFuture<String> hello(String url, String runtime) {
+        return hello(url, runtime, null);
+    }
+
+    /**
+     * Hello to fetch the web content of the param `url`, in the runtime of the
+     * name `runtime`, a `null` runtime means the default one.
+     * The fetching is aborted in Rust if it's not done within the `timeout`,
+     * and the returned future is completed exceptionally with a
+     * {@link RustTimeoutException}. A `null` timeout means no deadline.
+     */
+    public CompletableFuture<String> hello(String url, String runtime, Duration timeout) {
+        CompletableFuture<String> future = new CompletableFuture<>();
+        long taskId = nativeHello(future, url, runtime, toMillis(timeout));
+        return cancelOnFailure(future, taskId);
+    }
+
+    private native long nativeHello(CompletableFuture<String> future, String url, String runtime,
+            long timeoutMillis);
+
+    /**
+     * Hello to fetch the web content of the param `url`, as the raw bytes that
+     * are not copied out of Rust. The returned buffer must be closed when it's
+     * no longer used.
+     */
+    public CompletableFuture<NativeBuffer> helloBytes(String url) {
+        CompletableFuture<NativeBuffer> future = new CompletableFuture<>();
+        long taskId = nativeHelloBytes(future, url);
+        return cancelOnFailure(future, taskId);
+    }
+
+    private native long nativeHelloBytes(CompletableFuture<NativeBuffer> future, String url);
+
+    /**
+     * Read the numbers in [`start`, `end`) from Rust as Arrow record batches of
+     * a single BIGINT column "value", each batch has at most `batchSize` rows.
+     * The batches are imported through the Arrow C stream interface, without
+     * being copied, and are allocated in the `allocator`. The returned reader
+     * must be closed when it's no longer used.
+     */
+    public CompletableFuture<ArrowReader> range(BufferAllocator allocator, long start, long end, long batchSize) {
+        ArrowArrayStream stream = ArrowArrayStream.allocateNew(allocator);
+        CompletableFuture<Void> exported = new CompletableFuture<>();
+        try {
+            nativeRange(exported, stream.memoryAddress(), start, end, batchSize);
+        } catch (Throwable e) {
+            stream.close();
+            throw e;
+        }
+
+        // Unlike the other async operations, the Rust task is not cancelled with the returned
+        // future: the task is writing to the `stream`, so it must be done before the stream is
+        // closed here. The task finishes quickly anyway, since the batches are built lazily when
+        // they are loaded from the reader.
+        CompletableFuture<ArrowReader> future = new CompletableFuture<>();
+        exported.whenComplete((r, e) -> {
+            try (stream) {
+                if (e != null) {
+                    future.completeExceptionally(e);
+                    return;
+                }
+                ArrowReader reader = Data.importArrayStream(allocator, stream);
+                if (!future.complete(reader)) {
+                    reader.close();
+                }
+            } catch (Throwable t) {
+                future.completeExceptionally(t);
+            }
+        });
+        return future;
+    }
+
+    private native long nativeRange(CompletableFuture<Void> future, long streamAddress, long start, long end,
+            long batchSize);
+
+    /**
+     * Fetch the param `url`, like {@link #hello(String)}, but with the status
+     * and the headers of the response as well.
+     */
+    public CompletableFuture<HttpResponse> fetch(String url) {
+        CompletableFuture<HttpResponse> future = new CompletableFuture<>();
+        long taskId = nativeFetch(future, url);
+        return cancelOnFailure(future, taskId);
+    }
+
+    private native long nativeFetch(CompletableFuture<HttpResponse> future, String url);
+
+    /**
+     * Hello to fetch the web content of the param `url` as a stream of body
+     * chunks, instead of buffering all of it in memory. The fetching starts
+     * when the returned publisher is subscribed, and the chunks are read from
+     * the connection only as fast as the subscriber requests them.
+     */
+    public Flow.Publisher<byte[]> helloStream(String url) {
+        return helloStream(url, null);
+    }
+
+    /**
+     * Like {@link #helloStream(String)}, in the runtime of the name `runtime`,
+     * a `null` runtime means the default one.
+     */
+    public Flow.Publisher<byte[]> helloStream(String url, String runtime) {
+        return new RustPublisher<>(publisher -> nativeHelloStream(publisher, url, runtime));
     }
 
-    private native long nativeHello(String url);
+    private native long nativeHelloStream(RustPublisher<byte[]> publisher, String url, String runtime);
+
+    /**
+     * Echo the `value` back through the async bridge. It does nothing else in
+     * Rust, so it's mostly useful to measure the overhead of the bridge itself.
+     */
+    public CompletableFuture<String> echo(String value) {
+        CompletableFuture<String> future = new CompletableFuture<>();
+        long taskId = nativeEcho(future, value);
+        return cancelOnFailure(future, taskId);
+    }
+
+    private native long nativeEcho(CompletableFuture<String> future, String value);
+
+    // The async operations in Rust work like this:
+    //   1. The Java side creates a [CompletableFuture] and passes it to Rust.
+    //   2. The Rust side holds the future, spawns a task to do the async operation, and returns
+    //      the id of the task.
+    //   3. When the async operation is done, the Rust side "completes" the future directly, hence
+    //      the future chain is initiated in Java side.
+    //   4. If the future is completed in the Java side instead, like being cancelled or timed out,
+    //      the Rust side is told to `cancel` the task.
+    private static <T> CompletableFuture<T> cancelOnFailure(CompletableFuture<T> future, long taskId) {
+        future.whenComplete((r, e) -> {
+            if (e != null) {
+                // No-op if the task is already done in Rust.
+                cancel(taskId);
+            }
+        });
+        return future;
+    }
+
+    /**
+     * Abort the task in Rust that is to complete a future.
+     */
+    private static native void cancel(long taskId);
+
+    /**
+     * Called by the Rust side when the lib is loaded, to name the Java classes
+     * that it calls back or registers the native methods to. They could be
+     * relocated along with this class, e.g. by shading. The keys are the names
+     * relative to the original package.
+     */
+    private static Map<String, String> resolveCallbackClasses() {
+        Map<String, String> classes = new HashMap<>();
+        classes.put("RustJavaDemo", RustJavaDemo.class.getName());
+        classes.put("utils/Logger", Logger.class.getName());
+        classes.put("utils/RustPublisher", RustPublisher.class.getName());
+        classes.put("NativeBuffer", NativeBuffer.class.getName());
+        classes.put("HttpResponse", HttpResponse.class.getName());
+        classes.put("RuntimeConfig", RuntimeConfig.class.getName());
+        classes.put("RuntimeMetrics", RuntimeMetrics.class.getName());
+        classes.put("RustException", RustException.class.getName());
+        classes.put("RustJniException", RustJniException.class.getName());
+        classes.put("RustHttpException", RustHttpException.class.getName());
+        classes.put("RustTimeoutException", RustTimeoutException.class.getName());
+        return classes;
+    }
+
+    private static long toMillis(Duration timeout) {
+        if (timeout == null) {
+            return 0;
+        }
+        if (timeout.isNegative() || timeout.isZero()) {
+            throw new IllegalArgumentException("`timeout` must be positive");
+        }
+        return Math.max(timeout.toMillis(), 1);
+    }
 }
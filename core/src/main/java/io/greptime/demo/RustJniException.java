@@ -0,0 +1,27 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+/**
+ * Thrown when the calls between Rust and Java fail, e.g. a Java method that is
+ * called from Rust throws. It's usually a bug rather than a transient failure,
+ * so retrying it is unlikely to help.
+ */
+public class RustJniException extends RustException {
+
+    public RustJniException(String message, String location) {
+        super(message, location);
+    }
+}
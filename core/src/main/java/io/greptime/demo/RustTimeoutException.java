@@ -0,0 +1,38 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+import java.time.Duration;
+
+/**
+ * Thrown when an async operation in Rust is not done before its deadline. The
+ * operation is aborted in the Rust side then.
+ */
+public class RustTimeoutException extends RustException {
+
+    private final long timeoutMillis;
+
+    public RustTimeoutException(String message, String location, long timeoutMillis) {
+        super(message, location);
+        this.timeoutMillis = timeoutMillis;
+    }
+
+    /**
+     * The deadline that the operation missed.
+     */
+    public Duration getTimeout() {
+        return Duration.ofMillis(this.timeoutMillis);
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo.utils;
+
+import java.util.concurrent.Flow;
+import java.util.concurrent.atomic.AtomicBoolean;
+import java.util.function.ToLongFunction;
+
+/**
+ * A {@link Flow.Publisher} of the items of a stream in Rust.
+ *
+ * The publisher is cold and can only be subscribed once: the stream is started
+ * in Rust when it's subscribed. The Rust side pulls an item from the stream
+ * only when the subscriber has requested more, so the subscriber's
+ * `request(n)` is the backpressure all the way down to the stream. Cancelling
+ * the subscription drops the stream in Rust.
+ */
+public class RustPublisher<T> implements Flow.Publisher<T> {
+
+    private final ToLongFunction<RustPublisher<T>> starter;
+    private final AtomicBoolean subscribed = new AtomicBoolean();
+    private final AtomicBoolean done = new AtomicBoolean();
+
+    private volatile Flow.Subscriber<? super T> subscriber;
+    private volatile long streamId;
+
+    /**
+     * The `starter` starts the stream in Rust, which pushes the items to the
+     * publisher, and returns the id of the stream.
+     */
+    public RustPublisher(ToLongFunction<RustPublisher<T>> starter) {
+        this.starter = starter;
+    }
+
+    @Override
+    public void subscribe(Flow.Subscriber<? super T> subscriber) {
+        if (!this.subscribed.compareAndSet(false, true)) {
+            subscriber.onSubscribe(NoopSubscription.INSTANCE);
+            subscriber.onError(new IllegalStateException("RustPublisher can only be subscribed once"));
+            return;
+        }
+
+        this.subscriber = subscriber;
+        try {
+            // Nothing is pushed before the first `request`, so it's safe to start the stream
+            // before `onSubscribe`.
+            this.streamId = this.starter.applyAsLong(this);
+        } catch (Throwable e) {
+            this.done.set(true);
+            subscriber.onSubscribe(NoopSubscription.INSTANCE);
+            subscriber.onError(e);
+            return;
+        }
+        subscriber.onSubscribe(new Subscription());
+    }
+
+    // Called by the Rust side.
+    void onNext(T item) {
+        if (!this.done.get()) {
+            this.subscriber.onNext(item);
+        }
+    }
+
+    // Called by the Rust side.
+    void onError(Throwable e) {
+        if (this.done.compareAndSet(false, true)) {
+            this.subscriber.onError(e);
+        }
+    }
+
+    // Called by the Rust side.
+    void onComplete() {
+        if (this.done.compareAndSet(false, true)) {
+            this.subscriber.onComplete();
+        }
+    }
+
+    private class Subscription implements Flow.Subscription {
+
+        @Override
+        public void request(long n) {
+            if (done.get()) {
+                return;
+            }
+            if (n <= 0) {
+                cancel();
+                subscriber.onError(new IllegalArgumentException("`n` must be positive, got " + n));
+                return;
+            }
+            RustPublisher.request(streamId, n);
+        }
+
+        @Override
+        public void cancel() {
+            if (done.compareAndSet(false, true)) {
+                RustPublisher.cancel(streamId);
+            }
+        }
+    }
+
+    private enum NoopSubscription implements Flow.Subscription {
+        INSTANCE;
+
+        @Override
+        public void request(long n) {}
+
+        @Override
+        public void cancel() {}
+    }
+
+    /**
+     * Add `n` to the demand of the stream in Rust. No-op if the stream is done.
+     */
+    private static native void request(long streamId, long n);
+
+    /**
+     * Drop the stream in Rust. No-op if the stream is done.
+     */
+    private static native void cancel(long streamId);
+}
@@ -0,0 +1,195 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The proc macros of the demo Rust lib.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Result};
+
+/// Maps a Rust struct to a Java class, see the `java_object` module in the demo Rust lib.
+#[proc_macro_derive(JavaObject, attributes(java))]
+pub fn derive_java_object(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_java_object(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_java_object(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "JavaObject cannot be derived for generic structs",
+        ));
+    }
+
+    let class = java_attr(&input.attrs, "class")?
+        .ok_or_else(|| Error::new_spanned(ident, "missing `#[java(class = \"...\")]`"))?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    ident,
+                    "JavaObject can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "JavaObject can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut field_idents = Vec::with_capacity(fields.len());
+    let mut field_types = Vec::with_capacity(fields.len());
+    let mut java_names = Vec::with_capacity(fields.len());
+    for field in fields {
+        let field_ident = field.ident.as_ref().unwrap();
+        let java_name = match java_attr(&field.attrs, "name")? {
+            Some(name) => name,
+            None => LitStr::new(&camel_case(&field_ident.to_string()), field_ident.span()),
+        };
+        field_idents.push(field_ident);
+        field_types.push(&field.ty);
+        java_names.push(java_name);
+    }
+    let indices = 0..fields.len();
+
+    Ok(quote! {
+        const _: () = {
+            use ::snafu::ResultExt;
+            use crate::convert::{FromJava, IntoJava, JavaField};
+            use crate::error::JniSnafu;
+            use crate::java_object::{JavaClass, JavaObject};
+
+            static CLASS: crate::cache::Cache<JavaClass> = crate::cache::Cache::new();
+
+            fn class() -> ::std::sync::Arc<JavaClass> {
+                CLASS.get().unwrap_or_else(|| {
+                    panic!(
+                        "JavaObject '{}' must to be initialized in 'JNI_OnLoad' or 'libInit'!",
+                        stringify!(#ident)
+                    )
+                })
+            }
+
+            impl JavaObject for #ident {
+                fn init(env: &mut ::jni::JNIEnv) -> ::jni::errors::Result<()> {
+                    CLASS.get_or_try_init(|| {
+                        JavaClass::try_new(
+                            env,
+                            #class,
+                            &[#((#java_names, <#field_types as JavaField>::SIGNATURE)),*],
+                        )
+                    })?;
+                    Ok(())
+                }
+
+                fn release() {
+                    CLASS.release();
+                }
+            }
+
+            impl IntoJava for #ident {
+                const SIGNATURE: &'static str = concat!("L", #class, ";");
+
+                fn into_java<'a>(
+                    self,
+                    env: &mut ::jni::JNIEnv<'a>,
+                ) -> crate::error::Result<::jni::objects::JObject<'a>> {
+                    let class = class();
+                    let args = vec![#(JavaField::into_jvalue(self.#field_idents, env)?),*];
+                    class.new_object(env, args)
+                }
+            }
+
+            impl FromJava for #ident {
+                fn from_java(
+                    env: &mut ::jni::JNIEnv,
+                    obj: &::jni::objects::JObject,
+                ) -> crate::error::Result<Self> {
+                    let class = class();
+                    class.ensure_not_null(obj)?;
+                    Ok(Self {
+                        #(#field_idents: JavaField::get_field(env, obj, class.field(#indices))?),*
+                    })
+                }
+            }
+
+            impl JavaField for #ident {
+                const SIGNATURE: &'static str = <Self as IntoJava>::SIGNATURE;
+
+                fn into_jvalue<'a>(
+                    self,
+                    env: &mut ::jni::JNIEnv<'a>,
+                ) -> crate::error::Result<::jni::objects::JValueOwned<'a>> {
+                    self.into_java(env).map(::jni::objects::JValueOwned::Object)
+                }
+
+                fn get_field(
+                    env: &mut ::jni::JNIEnv,
+                    obj: &::jni::objects::JObject,
+                    field: ::jni::objects::JFieldID,
+                ) -> crate::error::Result<Self> {
+                    // Safety: the field is looked up by the signature of `Self`.
+                    let value = unsafe {
+                        env.get_field_unchecked(obj, field, ::jni::signature::ReturnType::Object)
+                    };
+                    let value = value.and_then(|x| x.l()).context(JniSnafu)?;
+                    let value = env.auto_local(value);
+                    Self::from_java(env, &value)
+                }
+            }
+        };
+    })
+}
+
+/// Gets the value of `#[java(key = "value")]`.
+fn java_attr(attrs: &[syn::Attribute], key: &str) -> Result<Option<LitStr>> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|x| x.path().is_ident("java")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                value = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error(format!("unsupported java attribute, expected `{key}`")))
+            }
+        })?;
+    }
+    Ok(value)
+}
+
+/// Converts a Rust field name to the Java one, e.g. "content_type" to "contentType".
+fn camel_case(name: &str) -> String {
+    let mut java_name = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.trim_start_matches("r#").chars() {
+        if c == '_' {
+            upper = !java_name.is_empty();
+        } else if upper {
+            java_name.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            java_name.push(c);
+        }
+    }
+    java_name
+}
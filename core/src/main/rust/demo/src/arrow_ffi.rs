@@ -0,0 +1,89 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hands the Arrow data to Java through the [Arrow C stream interface], so that Java's
+//! `arrow-c-data` can import it without copying.
+//!
+//! The Java side allocates an empty `ArrowArrayStream` struct, and passes down its address. The
+//! Rust task exports the [RecordBatchReader] into it, then completes the Java future, by when the
+//! Java side can import the struct as an `ArrowReader`. The batches are pulled from the reader
+//! lazily, as the Java side loads them.
+//!
+//! [Arrow C stream interface]: https://arrow.apache.org/docs/format/CStreamInterface.html
+
+#[cfg(test)]
+mod test;
+
+use std::sync::Arc;
+
+use arrow::array::Int64Array;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ffi_stream::FFI_ArrowArrayStream;
+use arrow::record_batch::{RecordBatch, RecordBatchIterator, RecordBatchReader};
+use snafu::ensure;
+
+use crate::error::{InvalidArgumentSnafu, Result};
+
+/// Exports the `reader` to the `ArrowArrayStream` struct at the address `stream`.
+///
+/// # Safety
+///
+/// The `stream` must point to a valid, released (or zeroed) `ArrowArrayStream` struct, which is
+/// owned by the Java side.
+pub(crate) unsafe fn export_reader(
+    reader: Box<dyn RecordBatchReader + Send>,
+    stream: *mut FFI_ArrowArrayStream,
+) {
+    // Don't drop the struct in place, it's released by the Java side, if it's ever imported.
+    std::ptr::write(stream, FFI_ArrowArrayStream::new(reader));
+}
+
+/// A reader of the single Int64 column "value", containing the numbers in `start..end`, in batches
+/// of the `batch_size`.
+pub(crate) fn range_reader(
+    start: i64,
+    end: i64,
+    batch_size: usize,
+) -> impl RecordBatchReader + Send + 'static {
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "value",
+        DataType::Int64,
+        false,
+    )]));
+
+    let batch_schema = schema.clone();
+    let batches = (start..end).step_by(batch_size).map(move |from| {
+        let to = from.saturating_add(batch_size as i64).min(end);
+        let values = Int64Array::from_iter_values(from..to);
+        RecordBatch::try_new(batch_schema.clone(), vec![Arc::new(values)])
+    });
+    RecordBatchIterator::new(batches, schema)
+}
+
+/// Checks the arguments of [range_reader].
+pub(crate) fn check_range(start: i64, end: i64, batch_size: i64) -> Result<usize> {
+    ensure!(
+        start <= end,
+        InvalidArgumentSnafu {
+            reason: format!("`start` ({start}) cannot be greater than `end` ({end})"),
+        }
+    );
+    ensure!(
+        batch_size > 0,
+        InvalidArgumentSnafu {
+            reason: format!("`batch_size` must be positive, got {batch_size}"),
+        }
+    );
+    Ok(batch_size as usize)
+}
@@ -0,0 +1,56 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::AsArray;
+use arrow::datatypes::Int64Type;
+use arrow::ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream};
+use arrow::record_batch::RecordBatchReader;
+
+use super::{check_range, export_reader, range_reader};
+
+/// Exports the reader like the Java side does, and imports it back.
+fn export_and_import(start: i64, end: i64, batch_size: usize) -> ArrowArrayStreamReader {
+    let mut stream = FFI_ArrowArrayStream::empty();
+    unsafe { export_reader(Box::new(range_reader(start, end, batch_size)), &mut stream) };
+    ArrowArrayStreamReader::try_new(stream).unwrap()
+}
+
+#[test]
+fn test_export_reader() {
+    let reader = export_and_import(-3, 7, 4);
+    assert_eq!(reader.schema().field(0).name(), "value");
+
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let sizes = batches.iter().map(|x| x.num_rows()).collect::<Vec<_>>();
+    assert_eq!(sizes, vec![4, 4, 2]);
+
+    let values = batches
+        .iter()
+        .flat_map(|x| x.column(0).as_primitive::<Int64Type>().values().to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(values, (-3..7).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_export_empty_reader() {
+    let mut reader = export_and_import(5, 5, 4);
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn test_check_range() {
+    assert_eq!(check_range(0, 10, 3).unwrap(), 3);
+    assert!(check_range(10, 0, 3).is_err());
+    assert!(check_range(0, 10, 0).is_err());
+}
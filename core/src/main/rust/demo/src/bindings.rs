@@ -0,0 +1,405 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The typed bindings of the Java methods that are called from Rust (the "upcalls"), declared by
+//! the [java_bindings](crate::java_bindings) macro.
+
+#[cfg(test)]
+mod test;
+
+use jni::errors::Result;
+use jni::objects::{JValue, JValueOwned};
+use jni::sys::{jbyte, jchar, jdouble, jfloat, jint, jlong, jshort};
+
+/// A type of the arguments or the return values of the Java methods in the bindings. They are
+/// either the primitives (or `()` for the `void`), or the classes declared in the bindings, which
+/// are passed as [JObject](jni::objects::JObject)s.
+///
+/// The JNI signatures of the methods are derived from these types, so the arguments can never
+/// mismatch the signatures.
+pub(crate) trait JniType {
+    /// The JNI type signature, like "I" or "Ljava/lang/String;".
+    const SIGNATURE: &'static str;
+
+    /// The type of the argument in Rust.
+    type Arg<'r>;
+
+    /// The type of the return value in Rust.
+    type Ret<'l>;
+
+    fn as_jvalue<'r>(arg: &Self::Arg<'r>) -> JValue<'r, 'r>;
+
+    fn from_jvalue(value: JValueOwned) -> Result<Self::Ret<'_>>;
+}
+
+macro_rules! impl_primitive {
+    ($ty:ty, $sig:literal, $variant:ident, $getter:ident) => {
+        impl JniType for $ty {
+            const SIGNATURE: &'static str = $sig;
+
+            type Arg<'r> = $ty;
+
+            type Ret<'l> = $ty;
+
+            fn as_jvalue<'r>(arg: &Self::Arg<'r>) -> JValue<'r, 'r> {
+                JValue::$variant((*arg).into())
+            }
+
+            fn from_jvalue(value: JValueOwned) -> Result<Self::Ret<'_>> {
+                value.$getter()
+            }
+        }
+    };
+}
+
+impl_primitive!(bool, "Z", Bool, z);
+impl_primitive!(jbyte, "B", Byte, b);
+impl_primitive!(jchar, "C", Char, c);
+impl_primitive!(jshort, "S", Short, s);
+impl_primitive!(jint, "I", Int, i);
+impl_primitive!(jlong, "J", Long, j);
+impl_primitive!(jfloat, "F", Float, f);
+impl_primitive!(jdouble, "D", Double, d);
+
+impl JniType for () {
+    const SIGNATURE: &'static str = "V";
+
+    type Arg<'r> = ();
+
+    type Ret<'l> = ();
+
+    fn as_jvalue<'r>(_arg: &Self::Arg<'r>) -> JValue<'r, 'r> {
+        unreachable!("`void` cannot be an argument")
+    }
+
+    fn from_jvalue(value: JValueOwned) -> Result<Self::Ret<'_>> {
+        value.v()
+    }
+}
+
+/// Declares the bindings of the Java classes and their methods:
+///
+/// ```ignore
+/// java_bindings! {
+///     class JavaString = "java/lang/String" {}
+///
+///     class Logger = "io/greptime/demo/utils/Logger" {
+///         static fn getLogger(name: JavaString) -> Logger;
+///         fn info(msg: JavaString);
+///     }
+///
+///     class RuntimeConfig = "io/greptime/demo/RuntimeConfig" {
+///         field workerThreads: jint;
+///     }
+/// }
+/// ```
+///
+/// For each class, a type (`Logger`) of the same name is declared to be used in the signatures of
+/// the methods. And for each method, an invoker ([StaticMethodInvoker] or [MethodInvoker]) is
+/// cached, with a typed wrapper to call it, e.g. `Logger::getLogger(env, &name)`, or
+/// `Logger::info(env, &logger, &msg)` for the instance methods. The overloaded methods are not
+/// supported.
+///
+/// The classes are passed as [JObject](jni::objects::JObject)s, so the wrappers are `unsafe`: the
+/// receiver and the object arguments must be instances of the declared classes (or `null`s for
+/// the arguments), which can't be checked at compile time.
+///
+/// For each field, an accessor ([StaticFieldAccessor] or [FieldAccessor]) is cached, and is
+/// returned by a function of the same name, e.g. `RuntimeConfig::workerThreads().get(env, &obj)`.
+///
+/// An `init` function is declared as well, to cache all the invokers and accessors, it must be
+/// called in the `JNI_OnLoad`. And the `release` and `release_all` functions to release them.
+///
+/// [StaticMethodInvoker]: crate::method_invoker::StaticMethodInvoker
+/// [MethodInvoker]: crate::method_invoker::MethodInvoker
+/// [StaticFieldAccessor]: crate::field_accessor::StaticFieldAccessor
+/// [FieldAccessor]: crate::field_accessor::FieldAccessor
+#[macro_export]
+macro_rules! java_bindings {
+    // Normalizes the methods to `(static|instance name (args) -> (ret))`, and the fields to
+    // `(static|instance name: type)`.
+    (@class $metas:tt $class:ident $path:literal [$($methods:tt)*] $fields:tt
+        static fn $method:ident($($arg:ident: $arg_ty:ty),* $(,)?) $(-> $ret:ty)?;
+        $($rest:tt)*
+    ) => {
+        $crate::java_bindings!(@class $metas $class $path
+            [$($methods)* (static $method ($($arg: $arg_ty),*) -> ($($ret)?))] $fields
+            $($rest)*
+        );
+    };
+    (@class $metas:tt $class:ident $path:literal [$($methods:tt)*] $fields:tt
+        fn $method:ident($($arg:ident: $arg_ty:ty),* $(,)?) $(-> $ret:ty)?;
+        $($rest:tt)*
+    ) => {
+        $crate::java_bindings!(@class $metas $class $path
+            [$($methods)* (instance $method ($($arg: $arg_ty),*) -> ($($ret)?))] $fields
+            $($rest)*
+        );
+    };
+    (@class $metas:tt $class:ident $path:literal $methods:tt [$($fields:tt)*]
+        static field $field:ident: $field_ty:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::java_bindings!(@class $metas $class $path
+            $methods [$($fields)* (static $field: $field_ty)]
+            $($rest)*
+        );
+    };
+    (@class $metas:tt $class:ident $path:literal $methods:tt [$($fields:tt)*]
+        field $field:ident: $field_ty:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::java_bindings!(@class $metas $class $path
+            $methods [$($fields)* (instance $field: $field_ty)]
+            $($rest)*
+        );
+    };
+    (@class [$($meta:tt)*] $class:ident $path:literal
+        [$(($kind:ident $method:ident ($($arg:ident: $arg_ty:ty),*) -> ($($ret:ty)?)))*]
+        [$(($field_kind:ident $field:ident: $field_ty:ty))*]
+    ) => {
+        $($meta)*
+        pub(crate) struct $class;
+
+        impl $crate::bindings::JniType for $class {
+            const SIGNATURE: &'static str = concat!("L", $path, ";");
+
+            type Arg<'r> = &'r ::jni::objects::JObject<'r>;
+
+            type Ret<'l> = ::jni::objects::JObject<'l>;
+
+            fn as_jvalue<'r>(arg: &Self::Arg<'r>) -> ::jni::objects::JValue<'r, 'r> {
+                ::jni::objects::JValue::Object(*arg)
+            }
+
+            fn from_jvalue(
+                value: ::jni::objects::JValueOwned,
+            ) -> ::jni::errors::Result<Self::Ret<'_>> {
+                value.l()
+            }
+        }
+
+        const _: () = {
+            $(
+                #[allow(non_upper_case_globals)]
+                static $method: $crate::cache::Cache<$crate::java_bindings!(@invoker $kind)> =
+                    $crate::cache::Cache::new();
+            )*
+            $(
+                #[allow(non_upper_case_globals)]
+                static $field: $crate::cache::Cache<
+                    $crate::java_bindings!(@accessor $field_kind $field_ty),
+                > = $crate::cache::Cache::new();
+            )*
+
+            impl $class {
+                // The `env` is unused if there's no method or field.
+                #[allow(unused_variables)]
+                pub(crate) fn init(env: &mut ::jni::JNIEnv) -> ::jni::errors::Result<()> {
+                    $(
+                        $method.get_or_try_init(|| {
+                            let sig = [
+                                "(",
+                                $(<$arg_ty as $crate::bindings::JniType>::SIGNATURE,)*
+                                ")",
+                                <$crate::java_bindings!(@ret $($ret)?) as $crate::bindings::JniType>::SIGNATURE,
+                            ]
+                            .concat();
+                            <$crate::java_bindings!(@invoker $kind)>::try_new(
+                                env,
+                                $path,
+                                stringify!($method),
+                                &sig,
+                            )
+                        })?;
+                    )*
+                    $(
+                        $field.get_or_try_init(|| {
+                            <$crate::java_bindings!(@accessor $field_kind $field_ty)>::try_new(
+                                env,
+                                $path,
+                                stringify!($field),
+                            )
+                        })?;
+                    )*
+                    Ok(())
+                }
+
+                /// Releases the cached invokers and accessors.
+                pub(crate) fn release() {
+                    $($method.release();)*
+                    $($field.release();)*
+                }
+
+                $(
+                    $crate::java_bindings!(@wrapper $kind $path $method ($($arg: $arg_ty),*) -> ($($ret)?));
+                )*
+
+                $(
+                    #[allow(non_snake_case)]
+                    pub(crate) fn $field(
+                    ) -> ::std::sync::Arc<$crate::java_bindings!(@accessor $field_kind $field_ty)> {
+                        $crate::java_bindings!(@get $path $field)
+                    }
+                )*
+            }
+        };
+    };
+
+    (@invoker static) => { $crate::method_invoker::StaticMethodInvoker };
+    (@invoker instance) => { $crate::method_invoker::MethodInvoker };
+
+    (@accessor static $ty:ty) => { $crate::field_accessor::StaticFieldAccessor<$ty> };
+    (@accessor instance $ty:ty) => { $crate::field_accessor::FieldAccessor<$ty> };
+
+    (@ret) => { () };
+    (@ret $ret:ty) => { $ret };
+
+    (@wrapper static $path:literal $method:ident ($($arg:ident: $arg_ty:ty),*) -> ($($ret:ty)?)) => {
+        /// # Safety
+        ///
+        /// The object arguments must be instances of their declared classes, or `null`s.
+        #[allow(non_snake_case)]
+        pub(crate) unsafe fn $method<'l>(
+            env: &mut ::jni::JNIEnv<'l>,
+            $($arg: <$arg_ty as $crate::bindings::JniType>::Arg<'_>),*
+        ) -> ::jni::errors::Result<
+            <$crate::java_bindings!(@ret $($ret)?) as $crate::bindings::JniType>::Ret<'l>,
+        > {
+            let invoker = $crate::java_bindings!(@get $path $method);
+            let args = [$(<$arg_ty as $crate::bindings::JniType>::as_jvalue(&$arg)),*];
+            // Safety: the signature of the method is derived from the types of the arguments, and
+            // the caller makes sure the objects are of the declared classes.
+            let value = unsafe { invoker.invoke(env, &args) }?;
+            <$crate::java_bindings!(@ret $($ret)?) as $crate::bindings::JniType>::from_jvalue(value)
+        }
+    };
+    (@wrapper instance $path:literal $method:ident ($($arg:ident: $arg_ty:ty),*) -> ($($ret:ty)?)) => {
+        /// # Safety
+        ///
+        /// The `obj` must be an instance of the class, and the object arguments must be instances
+        /// of their declared classes, or `null`s.
+        #[allow(non_snake_case)]
+        pub(crate) unsafe fn $method<'l>(
+            env: &mut ::jni::JNIEnv<'l>,
+            obj: &::jni::objects::JObject,
+            $($arg: <$arg_ty as $crate::bindings::JniType>::Arg<'_>),*
+        ) -> ::jni::errors::Result<
+            <$crate::java_bindings!(@ret $($ret)?) as $crate::bindings::JniType>::Ret<'l>,
+        > {
+            let invoker = $crate::java_bindings!(@get $path $method);
+            let args = [$(<$arg_ty as $crate::bindings::JniType>::as_jvalue(&$arg)),*];
+            // Safety: the signature of the method is derived from the types of the arguments, and
+            // the caller makes sure the objects are of the declared classes.
+            let value = unsafe { invoker.invoke(env, obj, &args) }?;
+            <$crate::java_bindings!(@ret $($ret)?) as $crate::bindings::JniType>::from_jvalue(value)
+        }
+    };
+
+    (@get $path:literal $method:ident) => {
+        $method.get().unwrap_or_else(|| {
+            panic!(
+                "Java member '{}.{}' must to be initialized in 'JNI_OnLoad' or 'libInit'!",
+                $path,
+                stringify!($method)
+            );
+        })
+    };
+
+    (
+        $(
+            $(#[$meta:meta])*
+            class $class:ident = $path:literal { $($members:tt)* }
+        )*
+    ) => {
+        $(
+            $crate::java_bindings!(@class [$(#[$meta])*] $class $path [] [] $($members)*);
+        )*
+
+        /// Caches the invokers and accessors of all the bindings, must be called in the
+        /// `JNI_OnLoad`.
+        pub(crate) fn init(env: &mut ::jni::JNIEnv) -> ::jni::errors::Result<()> {
+            $($class::init(env)?;)*
+            Ok(())
+        }
+
+        /// Releases the cached invokers and accessors of the bindings of the lib's own classes, so
+        /// the classes can be unloaded. Those of the JDK classes are kept: the JDK classes never
+        /// pin the class loader of the lib, and they are still needed after the lib is shut down,
+        /// e.g. to fail the futures of the native calls that are made by then.
+        pub(crate) fn release() {
+            $(
+                if !$path.starts_with("java/") {
+                    $class::release();
+                }
+            )*
+        }
+
+        /// Releases the cached invokers and accessors of all the bindings, when the lib is
+        /// unloaded.
+        pub(crate) fn release_all() {
+            $($class::release();)*
+        }
+    };
+}
+
+java_bindings! {
+    class Object = "java/lang/Object" {}
+
+    class JavaString = "java/lang/String" {}
+
+    class ByteBuffer = "java/nio/ByteBuffer" {}
+
+    class Class = "java/lang/Class" {
+        fn getClassLoader() -> ClassLoader;
+    }
+
+    class ClassLoader = "java/lang/ClassLoader" {
+        fn loadClass(name: JavaString) -> Class;
+    }
+
+    class Throwable = "java/lang/Throwable" {
+        fn initCause(cause: Throwable) -> Throwable;
+    }
+
+    class CompletableFuture = "java/util/concurrent/CompletableFuture" {
+        fn complete(value: Object) -> bool;
+        fn completeExceptionally(ex: Throwable) -> bool;
+    }
+
+    class Logger = "io/greptime/demo/utils/Logger" {
+        static fn getLogger(name: JavaString) -> Logger;
+    }
+
+    class NativeBuffer = "io/greptime/demo/NativeBuffer" {
+        static fn wrap(buffer: ByteBuffer, handle: jlong) -> NativeBuffer;
+    }
+
+    class RuntimeConfig = "io/greptime/demo/RuntimeConfig" {
+        field workerThreads: jint;
+        field maxBlockingThreads: jint;
+        field threadStackSize: jlong;
+        field threadKeepAliveMillis: jlong;
+        field threadNamePrefix: JavaString;
+        field eventInterval: jint;
+        field globalQueueInterval: jint;
+        field currentThread: bool;
+    }
+
+    class RustPublisher = "io/greptime/demo/utils/RustPublisher" {
+        fn onNext(item: Object);
+        fn onError(ex: Throwable);
+        fn onComplete();
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use jni::objects::{JObject, JValue};
+
+use crate::test_util::jvm;
+
+mod math {
+    use jni::sys::{jint, jlong};
+
+    crate::java_bindings! {
+        class JavaString = "java/lang/String" {
+            static fn valueOf(value: jlong) -> JavaString;
+            fn length() -> jint;
+            fn concat(other: JavaString) -> JavaString;
+            fn isEmpty() -> bool;
+        }
+
+        class Math = "java/lang/Math" {
+            static fn max(a: jint, b: jint) -> jint;
+        }
+    }
+}
+
+mod release {
+    use crate::bindings::{JavaString, Object};
+
+    crate::java_bindings! {
+        class CompletableFuture = "java/util/concurrent/CompletableFuture" {
+            fn complete(value: Object) -> bool;
+        }
+
+        class ObjectName = "javax/management/ObjectName" {
+            fn getDomain() -> JavaString;
+        }
+    }
+}
+
+#[test]
+fn test_java_bindings() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    math::init(&mut env).unwrap();
+
+    let world = env.new_string("-world").unwrap();
+    // Safety: all the objects are `String`s.
+    let s = unsafe {
+        assert_eq!(math::Math::max(&mut env, -1, 7).unwrap(), 7);
+
+        let s = math::JavaString::valueOf(&mut env, 42).unwrap();
+        let s = math::JavaString::concat(&mut env, &s, &world).unwrap();
+        assert_eq!(math::JavaString::length(&mut env, &s).unwrap(), 8);
+        assert!(!math::JavaString::isEmpty(&mut env, &s).unwrap());
+        s
+    };
+
+    let s: String = env.get_string(&s.into()).unwrap().into();
+    assert_eq!(s, "42-world");
+}
+
+#[test]
+fn test_release_keeps_jdk_bindings() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    release::init(&mut env).unwrap();
+    // Like the `libShutdown` does, only the bindings of the JDK classes are kept.
+    release::release();
+
+    let future = env
+        .new_object("java/util/concurrent/CompletableFuture", "()V", &[])
+        .unwrap();
+    let done = env.new_string("done").unwrap();
+    // Safety: the `future` is a `CompletableFuture`, and the `done` can be any object.
+    assert!(unsafe { release::CompletableFuture::complete(&mut env, &future, &done) }.unwrap());
+    let value = env
+        .call_method(
+            &future,
+            "getNow",
+            "(Ljava/lang/Object;)Ljava/lang/Object;",
+            &[JValue::Object(&JObject::null())],
+        )
+        .and_then(|x| x.l())
+        .unwrap();
+    let value: String = env.get_string(&value.into()).unwrap().into();
+    assert_eq!(value, "done");
+
+    let domain = env.new_string("demo:type=Test").unwrap();
+    let name = env
+        .new_object(
+            "javax/management/ObjectName",
+            "(Ljava/lang/String;)V",
+            &[JValue::Object(&domain)],
+        )
+        .unwrap();
+    // Safety: the `name` is an `ObjectName`.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+        release::ObjectName::getDomain(&mut env, &name)
+    }));
+    assert!(result.is_err());
+
+    release::release_all();
+}
@@ -0,0 +1,75 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hands the Rust-owned bytes to Java as a direct `java.nio.ByteBuffer`, without copying them.
+//!
+//! The bytes are leaked as a `Box<Vec<u8>>` when they are wrapped in the Java `NativeBuffer`, whose
+//! `close` (or the `Cleaner` if it's never closed) calls back to [free] them.
+
+#[cfg(test)]
+mod test;
+
+use jni::objects::{JClass, JObject};
+use jni::sys::jlong;
+use jni::JNIEnv;
+use snafu::ResultExt;
+
+use crate::bindings;
+use crate::convert::IntoJava;
+use crate::error::{JniSnafu, Result};
+
+/// The bytes to be passed to Java as an `io.greptime.demo.NativeBuffer`.
+pub(crate) struct NativeBuffer(pub(crate) Vec<u8>);
+
+impl IntoJava for NativeBuffer {
+    const SIGNATURE: &'static str = "Lio/greptime/demo/NativeBuffer;";
+
+    fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        let (data, len, handle) = leak(self.0);
+        let result = wrap(env, data, len, handle);
+        if result.is_err() {
+            // Safety: the Java side doesn't own the handle yet.
+            unsafe { release(handle) };
+        }
+        result
+    }
+}
+
+fn wrap<'a>(env: &mut JNIEnv<'a>, data: *mut u8, len: usize, handle: jlong) -> Result<JObject<'a>> {
+    // Safety: the memory is valid until the `handle` is freed, which is owned by the `NativeBuffer`
+    // that holds the `ByteBuffer`.
+    let buffer = unsafe { env.new_direct_byte_buffer(data, len) }.context(JniSnafu)?;
+    let buffer = env.auto_local(buffer);
+    // Safety: the `buffer` is a `ByteBuffer`.
+    unsafe { bindings::NativeBuffer::wrap(env, &buffer, handle) }.context(JniSnafu)
+}
+
+/// Leaks the `bytes`, returns their address and length, and the handle to [release] them.
+fn leak(bytes: Vec<u8>) -> (*mut u8, usize, jlong) {
+    let bytes = Box::new(bytes);
+    let (data, len) = (bytes.as_ptr() as *mut u8, bytes.len());
+    (data, len, Box::into_raw(bytes) as jlong)
+}
+
+/// # Safety
+///
+/// The `handle` must be returned by [leak], and be released only once.
+unsafe fn release(handle: jlong) {
+    drop(Box::from_raw(handle as *mut Vec<u8>));
+}
+
+pub(crate) extern "system" fn free(_env: JNIEnv, _class: JClass, handle: jlong) {
+    // Safety: the `NativeBuffer` makes sure the handle is only freed once.
+    unsafe { release(handle) };
+}
@@ -0,0 +1,46 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jni::objects::{JClass, JObject};
+use jni::JNIEnv;
+
+use super::{free, leak};
+use crate::test_util::jvm;
+
+#[test]
+fn test_leak_and_free() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let bytes = (0..=255).collect::<Vec<u8>>();
+    let (data, len, handle) = leak(bytes.clone());
+    assert_eq!(len, bytes.len());
+
+    // Safety: the memory is valid until the `free` below.
+    let buffer = unsafe { env.new_direct_byte_buffer(data, len) }.unwrap();
+    assert_eq!(env.get_direct_buffer_capacity(&buffer).unwrap(), len);
+    assert_eq!(env.get_direct_buffer_address(&buffer).unwrap(), data);
+    // Read through the Java side.
+    for (i, byte) in bytes.iter().enumerate() {
+        let value = env
+            .call_method(&buffer, "get", "(I)B", &[(i as i32).into()])
+            .and_then(|x| x.b())
+            .unwrap();
+        assert_eq!(value as u8, *byte);
+    }
+    env.delete_local_ref(buffer).unwrap();
+
+    // Like the `NativeBuffer` frees it when it's closed or cleaned.
+    let free_env = unsafe { JNIEnv::from_raw(env.get_raw()) }.unwrap();
+    free(free_env, JClass::from(JObject::null()), handle);
+}
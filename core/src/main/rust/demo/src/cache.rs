@@ -0,0 +1,53 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod test;
+
+use std::sync::{Arc, RwLock};
+
+/// Like the [OnceLock](std::sync::OnceLock), but the cached value can be released.
+///
+/// The caches of the Java classes (and their methods and fields) hold the `GlobalRef`s of the
+/// classes, which keep the class loader of them from being garbage collected. They are released
+/// when the lib is shut down or unloaded, see `JNI_OnUnload`.
+pub(crate) struct Cache<T>(RwLock<Option<Arc<T>>>);
+
+impl<T> Cache<T> {
+    pub(crate) const fn new() -> Self {
+        Self(RwLock::new(None))
+    }
+
+    pub(crate) fn get(&self) -> Option<Arc<T>> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Gets the cached value, or initializes it by `f` if it's not cached (or is released).
+    pub(crate) fn get_or_try_init<E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<Arc<T>, E> {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        let mut value = self.0.write().unwrap();
+        if let Some(value) = value.as_ref() {
+            return Ok(value.clone());
+        }
+        Ok(value.insert(Arc::new(f()?)).clone())
+    }
+
+    /// Releases the cached value. It's dropped after all the clones of it are dropped.
+    pub(crate) fn release(&self) {
+        self.0.write().unwrap().take();
+    }
+}
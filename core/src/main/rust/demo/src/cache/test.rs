@@ -0,0 +1,44 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::Cache;
+
+#[test]
+fn test_cache() {
+    static CACHE: Cache<String> = Cache::new();
+    assert!(CACHE.get().is_none());
+
+    let result = CACHE.get_or_try_init(|| Err::<String, _>("failed"));
+    assert_eq!(result.unwrap_err(), "failed");
+    assert!(CACHE.get().is_none());
+
+    let value = CACHE
+        .get_or_try_init(|| Ok::<_, ()>("a".to_string()))
+        .unwrap();
+    assert_eq!(*value, "a");
+    let value = CACHE
+        .get_or_try_init(|| Ok::<_, ()>("b".to_string()))
+        .unwrap();
+    assert_eq!(*value, "a");
+
+    CACHE.release();
+    assert!(CACHE.get().is_none());
+    // The released value is still alive for its holders.
+    assert_eq!(*value, "a");
+
+    let value = CACHE
+        .get_or_try_init(|| Ok::<_, ()>("c".to_string()))
+        .unwrap();
+    assert_eq!(*value, "c");
+}
@@ -0,0 +1,101 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Looks up the Java classes by the class loader of `RustJavaDemo`.
+//!
+//! The threads of the tokio runtimes are attached to the JVM natively, and `FindClass` on them
+//! uses the system class loader, which can't find the classes of the application in, say, a
+//! Spring Boot fat jar, a servlet container or OSGi. So the class loader is captured in the
+//! `libInit`, and all the classes are loaded by it (and cached) instead.
+
+#[cfg(test)]
+mod test;
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use jni::errors::{Error, Result};
+use jni::objects::{GlobalRef, JClass, JObject};
+use jni::JNIEnv;
+
+use crate::cache::Cache;
+use crate::{bindings, classes};
+
+/// The class loader of `RustJavaDemo`, unset if it's the bootstrap one, or not captured yet.
+static CLASS_LOADER: Cache<GlobalRef> = Cache::new();
+
+/// The loaded classes, by their (resolved) names.
+static CLASSES: Mutex<BTreeMap<String, GlobalRef>> = Mutex::new(BTreeMap::new());
+
+/// Captures the class loader of the `class`.
+pub(crate) fn init(env: &mut JNIEnv, class: &JClass) -> Result<()> {
+    // Safety: the `class` is a `Class`.
+    let loader = unsafe { bindings::Class::getClassLoader(env, class) }?;
+    if loader.is_null() {
+        return Ok(());
+    }
+    let loader = env.new_global_ref(loader)?;
+    CLASS_LOADER.get_or_try_init(|| Ok::<_, Error>(loader))?;
+    Ok(())
+}
+
+/// Releases the class loader and the loaded classes.
+pub(crate) fn release() {
+    CLASS_LOADER.release();
+    CLASSES.lock().unwrap().clear();
+}
+
+/// Finds the class of the `name` in the JNI form, like "io/greptime/demo/NativeBuffer". The name
+/// is [resolved](classes::resolve) first.
+///
+/// It's loaded by the captured class loader, or by `FindClass` if there's none (which is the case
+/// in the `JNI_OnLoad`, where the class loader of the caller is used anyway).
+pub(crate) fn find_class(env: &mut JNIEnv, name: &str) -> Result<GlobalRef> {
+    find_class_by(env, CLASS_LOADER.get().as_deref(), &CLASSES, name)
+}
+
+/// [find_class] by the class `loader`, and caches the loaded classes in the `cache`.
+fn find_class_by(
+    env: &mut JNIEnv,
+    loader: Option<&GlobalRef>,
+    cache: &Mutex<BTreeMap<String, GlobalRef>>,
+    name: &str,
+) -> Result<GlobalRef> {
+    let name = classes::resolve(name);
+    if let Some(class) = cache.lock().unwrap().get(&*name) {
+        return Ok(class.clone());
+    }
+
+    let class = match loader {
+        Some(loader) => {
+            let binary_name = env.new_string(name.replace('/', "."))?;
+            let binary_name = env.auto_local(binary_name);
+            // Safety: the `loader` is a `ClassLoader`, and the `binary_name` is a `String`.
+            unsafe { bindings::ClassLoader::loadClass(env, loader, &binary_name) }?
+        }
+        None => JObject::from(env.find_class(&*name)?),
+    };
+    let class = env.auto_local(class);
+    let class = env.new_global_ref(&*class)?;
+
+    // Only cached between the `libInit` and the `libShutdown`, the classes must not be pinned
+    // after the lib is shut down.
+    if loader.is_some() {
+        cache
+            .lock()
+            .unwrap()
+            .insert(name.into_owned(), class.clone());
+    }
+    Ok(class)
+}
@@ -0,0 +1,93 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use super::find_class_by;
+use crate::bindings;
+use crate::test_util::jvm;
+
+#[test]
+fn test_find_class() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    bindings::ClassLoader::init(&mut env).unwrap();
+
+    let loader = env
+        .call_static_method(
+            "java/lang/ClassLoader",
+            "getSystemClassLoader",
+            "()Ljava/lang/ClassLoader;",
+            &[],
+        )
+        .and_then(|x| x.l())
+        .unwrap();
+    let loader = env.new_global_ref(loader).unwrap();
+    // Not the global ones, which are shared by the other tests.
+    let cache = Mutex::new(BTreeMap::new());
+
+    let class = find_class_by(
+        &mut env,
+        Some(&loader),
+        &cache,
+        "java/util/concurrent/ConcurrentSkipListMap",
+    )
+    .unwrap();
+    let expected = env
+        .find_class("java/util/concurrent/ConcurrentSkipListMap")
+        .unwrap();
+    assert!(env.is_same_object(&class, &expected).unwrap());
+
+    // Cached.
+    assert!(cache
+        .lock()
+        .unwrap()
+        .contains_key("java/util/concurrent/ConcurrentSkipListMap"));
+    let cached = find_class_by(
+        &mut env,
+        Some(&loader),
+        &cache,
+        "java/util/concurrent/ConcurrentSkipListMap",
+    )
+    .unwrap();
+    assert!(env.is_same_object(&class, &cached).unwrap());
+
+    assert!(find_class_by(
+        &mut env,
+        Some(&loader),
+        &cache,
+        "io/greptime/demo/NoSuchClass"
+    )
+    .is_err());
+    // The `ClassNotFoundException` thrown by the `loadClass`.
+    assert!(env.exception_occurred().is_some());
+    env.exception_clear();
+
+    // Not cached without a class loader, i.e. after the lib is shut down.
+    let class = find_class_by(
+        &mut env,
+        None,
+        &cache,
+        "java/util/concurrent/ConcurrentHashMap",
+    )
+    .unwrap();
+    let expected = env
+        .find_class("java/util/concurrent/ConcurrentHashMap")
+        .unwrap();
+    assert!(env.is_same_object(&class, &expected).unwrap());
+    assert!(!cache
+        .lock()
+        .unwrap()
+        .contains_key("java/util/concurrent/ConcurrentHashMap"));
+}
@@ -0,0 +1,99 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The names of the Java classes that are called from Rust (the "callback" classes). They could
+//! be relocated along with the `RustJavaDemo`, e.g. when the jar is shaded into another package,
+//! so the Java side names them in `RustJavaDemo.resolveCallbackClasses()`, which is called in the
+//! `JNI_OnLoad`. All the invokers, accessors and the other class lookups are resolved against
+//! them.
+
+#[cfg(test)]
+mod test;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use jni::JNIEnv;
+use snafu::ResultExt;
+
+use crate::convert::FromJava;
+use crate::error::{JniSnafu, Result};
+
+/// The original package of the Java classes, in the JNI form.
+const PACKAGE: &str = "io/greptime/demo/";
+
+/// The actual names of the callback classes, by their names relative to the [PACKAGE], like
+/// "utils/Logger".
+static CLASSES: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// Loads the names of the callback classes from the `RustJavaDemo` class of the `main_class` name.
+pub(crate) fn load(env: &mut JNIEnv, main_class: &str) -> Result<()> {
+    let classes = env
+        .call_static_method(
+            main_class,
+            "resolveCallbackClasses",
+            "()Ljava/util/Map;",
+            &[],
+        )
+        .and_then(|x| x.l())
+        .context(JniSnafu)?;
+    let classes = HashMap::<String, String>::from_java(env, &classes)?;
+
+    *CLASSES.write().unwrap() = classes
+        .into_iter()
+        .map(|(name, class)| (name, class.replace('.', "/")))
+        .collect();
+    Ok(())
+}
+
+/// Resolves the class `name` (in the JNI form) to the actual name of the callback class, or
+/// returns it as is if it's not one of them.
+pub(crate) fn resolve(name: &str) -> Cow<'_, str> {
+    resolve_in(&CLASSES.read().unwrap(), name)
+}
+
+/// Resolves all the class names in the JNI signature `sig`, like
+/// "(Ljava/lang/String;)Lio/greptime/demo/utils/Logger;".
+pub(crate) fn resolve_signature(sig: &str) -> Cow<'_, str> {
+    resolve_signature_in(&CLASSES.read().unwrap(), sig)
+}
+
+/// [resolve] against the callback `classes`.
+fn resolve_in<'a>(classes: &BTreeMap<String, String>, name: &'a str) -> Cow<'a, str> {
+    name.strip_prefix(PACKAGE)
+        .and_then(|x| classes.get(x).cloned())
+        .map_or(Cow::Borrowed(name), Cow::Owned)
+}
+
+/// [resolve_signature] against the callback `classes`.
+fn resolve_signature_in<'a>(classes: &BTreeMap<String, String>, sig: &'a str) -> Cow<'a, str> {
+    if !sig.contains(PACKAGE) {
+        return Cow::Borrowed(sig);
+    }
+
+    let mut resolved = String::with_capacity(sig.len());
+    let mut rest = sig;
+    // Only the class types ("L{name};") contain an 'L', the others are the primitives, '[', '('
+    // and ')'.
+    while let Some(start) = rest.find('L') {
+        resolved.push_str(&rest[..=start]);
+        rest = &rest[start + 1..];
+        let end = rest.find(';').unwrap_or(rest.len());
+        resolved.push_str(&resolve_in(classes, &rest[..end]));
+        rest = &rest[end..];
+    }
+    resolved.push_str(rest);
+    Cow::Owned(resolved)
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use super::{resolve_in, resolve_signature_in};
+
+#[test]
+fn test_resolve() {
+    // Not the global ones, which are shared by the other tests.
+    let classes = BTreeMap::from([
+        (
+            "utils/Logger".to_string(),
+            "com/ourco/shaded/greptime/demo/utils/Logger".to_string(),
+        ),
+        (
+            "NativeBuffer".to_string(),
+            "com/ourco/shaded/greptime/demo/NativeBuffer".to_string(),
+        ),
+    ]);
+    let resolve = |name| resolve_in(&classes, name);
+    let resolve_signature = |sig| resolve_signature_in(&classes, sig);
+
+    assert_eq!(
+        resolve("io/greptime/demo/utils/Logger"),
+        "com/ourco/shaded/greptime/demo/utils/Logger"
+    );
+    // Not a callback class.
+    assert_eq!(
+        resolve("io/greptime/demo/Unknown"),
+        "io/greptime/demo/Unknown"
+    );
+    assert_eq!(resolve("java/lang/String"), "java/lang/String");
+
+    assert_eq!(
+        resolve_signature("(Ljava/lang/String;)Lio/greptime/demo/utils/Logger;"),
+        "(Ljava/lang/String;)Lcom/ourco/shaded/greptime/demo/utils/Logger;"
+    );
+    assert_eq!(
+        resolve_signature("([Lio/greptime/demo/NativeBuffer;JLjava/util/List;)V"),
+        "([Lcom/ourco/shaded/greptime/demo/NativeBuffer;JLjava/util/List;)V"
+    );
+    assert_eq!(resolve_signature("(IJ)Z"), "(IJ)Z");
+}
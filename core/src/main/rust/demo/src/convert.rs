@@ -0,0 +1,373 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversions between the Rust values and the Java objects, so that the native functions don't
+//! have to hand-write the JNI glue for each of their arguments and results.
+//!
+//! The Java primitives are always boxed, e.g. an `i32` is converted to a `java.lang.Integer`. A
+//! Java `null` can only be converted from and to an [Option] (or a `()`).
+
+#[cfg(test)]
+mod test;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use jni::objects::{GlobalRef, JByteArray, JFieldID, JObject, JValue, JValueOwned};
+use jni::signature::{Primitive, ReturnType};
+use jni::sys::{jint, jsize};
+use jni::JNIEnv;
+use snafu::{ensure, IntoError, ResultExt};
+
+use crate::error::{InvalidUtf16Snafu, JniSnafu, Result, UnexpectedNullSnafu};
+
+/// Converts a Rust value to a Java object.
+pub(crate) trait IntoJava {
+    /// The type signature of the Java object, as it's declared in the fields or the method
+    /// parameters, e.g. "Ljava/util/List;" rather than "Ljava/util/ArrayList;".
+    const SIGNATURE: &'static str;
+
+    fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>>;
+}
+
+/// Converts a Java object to a Rust value.
+pub(crate) trait FromJava: Sized {
+    fn from_java(env: &mut JNIEnv, obj: &JObject) -> Result<Self>;
+}
+
+/// A Rust value that is a field of a Java object, see [crate::java_object::JavaObject].
+///
+/// Unlike [IntoJava] and [FromJava], the primitives are not boxed, e.g. an `i32` field is an `int`
+/// in Java.
+pub(crate) trait JavaField: Sized {
+    /// The type signature of the Java field.
+    const SIGNATURE: &'static str;
+
+    /// Converts the value to be passed as an argument of the Java constructor.
+    fn into_jvalue<'a>(self, env: &mut JNIEnv<'a>) -> Result<JValueOwned<'a>>;
+
+    /// Reads the `field` of the Java object `obj`, the `field` must be of the [Self::SIGNATURE].
+    fn get_field(env: &mut JNIEnv, obj: &JObject, field: JFieldID) -> Result<Self>;
+}
+
+fn ensure_not_null(obj: &JObject, java_type: &str) -> Result<()> {
+    ensure!(!obj.is_null(), UnexpectedNullSnafu { java_type });
+    Ok(())
+}
+
+macro_rules! impl_boxed_primitive {
+    ($rust:ty, $class:literal, $sig:literal, $unbox:literal, $variant:ident, $getter:ident) => {
+        impl IntoJava for $rust {
+            const SIGNATURE: &'static str = concat!("L", $class, ";");
+
+            fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+                env.call_static_method(
+                    $class,
+                    "valueOf",
+                    concat!("(", $sig, ")L", $class, ";"),
+                    &[JValue::$variant(self.into())],
+                )
+                .and_then(|x| x.l())
+                .context(JniSnafu)
+            }
+        }
+
+        impl FromJava for $rust {
+            fn from_java(env: &mut JNIEnv, obj: &JObject) -> Result<Self> {
+                ensure_not_null(obj, $class)?;
+                env.call_method(obj, $unbox, concat!("()", $sig), &[])
+                    .and_then(|x| x.$getter())
+                    .context(JniSnafu)
+            }
+        }
+    };
+}
+
+// There's no `u8` here, because the `Vec<u8>` is converted to a `byte[]` rather than a `List`.
+impl_boxed_primitive!(bool, "java/lang/Boolean", "Z", "booleanValue", Bool, z);
+impl_boxed_primitive!(i8, "java/lang/Byte", "B", "byteValue", Byte, b);
+impl_boxed_primitive!(i16, "java/lang/Short", "S", "shortValue", Short, s);
+impl_boxed_primitive!(i32, "java/lang/Integer", "I", "intValue", Int, i);
+impl_boxed_primitive!(i64, "java/lang/Long", "J", "longValue", Long, j);
+impl_boxed_primitive!(f32, "java/lang/Float", "F", "floatValue", Float, f);
+impl_boxed_primitive!(f64, "java/lang/Double", "D", "doubleValue", Double, d);
+
+/// A `()` is a `null`, like the Java `Void`.
+impl IntoJava for () {
+    const SIGNATURE: &'static str = "Ljava/lang/Void;";
+
+    fn into_java<'a>(self, _env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        Ok(JObject::null())
+    }
+}
+
+impl IntoJava for GlobalRef {
+    const SIGNATURE: &'static str = "Ljava/lang/Object;";
+
+    fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        env.new_local_ref(&self).context(JniSnafu)
+    }
+}
+
+// The strings are converted through their UTF-16 chars by the raw `NewString` and
+// `GetStringRegion`, rather than the `JNIEnv::new_string` and `JNIEnv::get_string`, which use the
+// JNI's "modified UTF-8" and silently replace the unpaired surrogates of the Java strings. The
+// UTF-16 conversions are exact in both directions, and an unpaired surrogate is an error.
+impl IntoJava for String {
+    const SIGNATURE: &'static str = "Ljava/lang/String;";
+
+    fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        let chars = self.encode_utf16().collect::<Vec<_>>();
+        let raw = env.get_raw();
+        // Safety: the `raw` is a valid `JNIEnv`, and the `chars` are of the passed length.
+        let s = unsafe { ((**raw).v1_1.NewString)(raw, chars.as_ptr(), chars.len() as jsize) };
+        // It's only null if an `OutOfMemoryError` is thrown.
+        if s.is_null() {
+            return Err(JniSnafu.into_error(jni::errors::Error::JavaException));
+        }
+        // Safety: the `s` is a new local reference.
+        Ok(unsafe { JObject::from_raw(s) })
+    }
+}
+
+impl FromJava for String {
+    fn from_java(env: &mut JNIEnv, obj: &JObject) -> Result<Self> {
+        ensure_not_null(obj, "java/lang/String")?;
+        let raw = env.get_raw();
+        // Safety: the `raw` is a valid `JNIEnv`, and the `obj` is a non-null `String`.
+        let len = unsafe { ((**raw).v1_1.GetStringLength)(raw, obj.as_raw()) };
+        let mut chars = vec![0; len as usize];
+        // Safety: same as above, and the `chars` are of the `len`, so the region is in bounds.
+        unsafe { ((**raw).v1_1.GetStringRegion)(raw, obj.as_raw(), 0, len, chars.as_mut_ptr()) };
+        String::from_utf16(&chars).context(InvalidUtf16Snafu)
+    }
+}
+
+impl IntoJava for Vec<u8> {
+    const SIGNATURE: &'static str = "[B";
+
+    fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        env.byte_array_from_slice(&self)
+            .map(JObject::from)
+            .context(JniSnafu)
+    }
+}
+
+impl FromJava for Vec<u8> {
+    fn from_java(env: &mut JNIEnv, obj: &JObject) -> Result<Self> {
+        ensure_not_null(obj, "byte[]")?;
+        env.convert_byte_array(<&JByteArray>::from(obj))
+            .context(JniSnafu)
+    }
+}
+
+impl<T: IntoJava> IntoJava for Option<T> {
+    const SIGNATURE: &'static str = T::SIGNATURE;
+
+    fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        match self {
+            Some(x) => x.into_java(env),
+            None => Ok(JObject::null()),
+        }
+    }
+}
+
+impl<T: FromJava> FromJava for Option<T> {
+    fn from_java(env: &mut JNIEnv, obj: &JObject) -> Result<Self> {
+        if obj.is_null() {
+            Ok(None)
+        } else {
+            T::from_java(env, obj).map(Some)
+        }
+    }
+}
+
+/// A `Vec` is converted to a `java.util.ArrayList`, and can be converted from any
+/// `java.util.List`.
+impl<T: IntoJava> IntoJava for Vec<T> {
+    const SIGNATURE: &'static str = "Ljava/util/List;";
+
+    fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        let list = env
+            .new_object(
+                "java/util/ArrayList",
+                "(I)V",
+                &[JValue::Int(self.len() as jint)],
+            )
+            .context(JniSnafu)?;
+        for x in self {
+            // Free the elements as soon as they are added, or a large list would overflow the
+            // local refs.
+            let x = x.into_java(env)?;
+            let x = env.auto_local(x);
+            env.call_method(&list, "add", "(Ljava/lang/Object;)Z", &[JValue::Object(&x)])
+                .context(JniSnafu)?;
+        }
+        Ok(list)
+    }
+}
+
+impl<T: FromJava> FromJava for Vec<T> {
+    fn from_java(env: &mut JNIEnv, obj: &JObject) -> Result<Self> {
+        ensure_not_null(obj, "java/util/List")?;
+        let size = env
+            .call_method(obj, "size", "()I", &[])
+            .and_then(|x| x.i())
+            .context(JniSnafu)?;
+
+        let mut vec = Vec::with_capacity(size as usize);
+        for i in 0..size {
+            let x = env
+                .call_method(obj, "get", "(I)Ljava/lang/Object;", &[JValue::Int(i)])
+                .and_then(|x| x.l())
+                .context(JniSnafu)?;
+            let x = env.auto_local(x);
+            vec.push(T::from_java(env, &x)?);
+        }
+        Ok(vec)
+    }
+}
+
+/// A `HashMap` is converted to a `java.util.HashMap`, and can be converted from any
+/// `java.util.Map`.
+impl<K: IntoJava, V: IntoJava> IntoJava for HashMap<K, V> {
+    const SIGNATURE: &'static str = "Ljava/util/Map;";
+
+    fn into_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        let map = env
+            .new_object(
+                "java/util/HashMap",
+                "(I)V",
+                &[JValue::Int(self.len() as jint)],
+            )
+            .context(JniSnafu)?;
+        for (k, v) in self {
+            let k = k.into_java(env)?;
+            let k = env.auto_local(k);
+            let v = v.into_java(env)?;
+            let v = env.auto_local(v);
+            let prev = env
+                .call_method(
+                    &map,
+                    "put",
+                    "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
+                    &[JValue::Object(&k), JValue::Object(&v)],
+                )
+                .and_then(|x| x.l())
+                .context(JniSnafu)?;
+            env.delete_local_ref(prev).context(JniSnafu)?;
+        }
+        Ok(map)
+    }
+}
+
+impl<K, V> FromJava for HashMap<K, V>
+where
+    K: FromJava + Eq + Hash,
+    V: FromJava,
+{
+    fn from_java(env: &mut JNIEnv, obj: &JObject) -> Result<Self> {
+        ensure_not_null(obj, "java/util/Map")?;
+        let entries = env
+            .call_method(obj, "entrySet", "()Ljava/util/Set;", &[])
+            .and_then(|x| x.l())
+            .context(JniSnafu)?;
+        let entries = env.auto_local(entries);
+        let iter = env
+            .call_method(&entries, "iterator", "()Ljava/util/Iterator;", &[])
+            .and_then(|x| x.l())
+            .context(JniSnafu)?;
+        let iter = env.auto_local(iter);
+
+        let mut map = HashMap::new();
+        while env
+            .call_method(&iter, "hasNext", "()Z", &[])
+            .and_then(|x| x.z())
+            .context(JniSnafu)?
+        {
+            let entry = env
+                .call_method(&iter, "next", "()Ljava/lang/Object;", &[])
+                .and_then(|x| x.l())
+                .context(JniSnafu)?;
+            let entry = env.auto_local(entry);
+            let k = env
+                .call_method(&entry, "getKey", "()Ljava/lang/Object;", &[])
+                .and_then(|x| x.l())
+                .context(JniSnafu)?;
+            let k = env.auto_local(k);
+            let v = env
+                .call_method(&entry, "getValue", "()Ljava/lang/Object;", &[])
+                .and_then(|x| x.l())
+                .context(JniSnafu)?;
+            let v = env.auto_local(v);
+            map.insert(K::from_java(env, &k)?, V::from_java(env, &v)?);
+        }
+        Ok(map)
+    }
+}
+
+macro_rules! impl_primitive_field {
+    ($rust:ty, $sig:literal, $primitive:ident, $variant:ident, $getter:ident) => {
+        impl JavaField for $rust {
+            const SIGNATURE: &'static str = $sig;
+
+            fn into_jvalue<'a>(self, _env: &mut JNIEnv<'a>) -> Result<JValueOwned<'a>> {
+                Ok(JValueOwned::$variant(self.into()))
+            }
+
+            fn get_field(env: &mut JNIEnv, obj: &JObject, field: JFieldID) -> Result<Self> {
+                let ty = ReturnType::Primitive(Primitive::$primitive);
+                // Safety: the field is looked up by the signature of `Self`.
+                unsafe { env.get_field_unchecked(obj, field, ty) }
+                    .and_then(|x| x.$getter())
+                    .context(JniSnafu)
+            }
+        }
+    };
+}
+
+impl_primitive_field!(bool, "Z", Boolean, Bool, z);
+impl_primitive_field!(i8, "B", Byte, Byte, b);
+impl_primitive_field!(i16, "S", Short, Short, s);
+impl_primitive_field!(i32, "I", Int, Int, i);
+impl_primitive_field!(i64, "J", Long, Long, j);
+impl_primitive_field!(f32, "F", Float, Float, f);
+impl_primitive_field!(f64, "D", Double, Double, d);
+
+macro_rules! impl_object_field {
+    ([$($generics:tt)*] $rust:ty) => {
+        impl<$($generics)*> JavaField for $rust {
+            const SIGNATURE: &'static str = <Self as IntoJava>::SIGNATURE;
+
+            fn into_jvalue<'a>(self, env: &mut JNIEnv<'a>) -> Result<JValueOwned<'a>> {
+                self.into_java(env).map(JValueOwned::Object)
+            }
+
+            fn get_field(env: &mut JNIEnv, obj: &JObject, field: JFieldID) -> Result<Self> {
+                // Safety: the field is looked up by the signature of `Self`.
+                let value = unsafe { env.get_field_unchecked(obj, field, ReturnType::Object) }
+                    .and_then(|x| x.l())
+                    .context(JniSnafu)?;
+                let value = env.auto_local(value);
+                Self::from_java(env, &value)
+            }
+        }
+    };
+}
+
+impl_object_field!([] String);
+impl_object_field!([] Vec<u8>);
+impl_object_field!([T: IntoJava + FromJava] Option<T>);
+impl_object_field!([T: IntoJava + FromJava] Vec<T>);
+impl_object_field!([K: IntoJava + FromJava + Eq + Hash, V: IntoJava + FromJava] HashMap<K, V>);
@@ -0,0 +1,231 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use jni::objects::{JObject, JValue};
+use jni::sys::jint;
+use jni::JNIEnv;
+
+use super::{FromJava, IntoJava};
+use crate::error::Error;
+use crate::test_util::jvm;
+
+fn round_trip<T>(env: &mut JNIEnv, value: T)
+where
+    T: IntoJava + FromJava + Clone + PartialEq + Debug,
+{
+    let obj = value.clone().into_java(env).unwrap();
+    assert_eq!(T::from_java(env, &obj).unwrap(), value);
+}
+
+fn class_name(env: &mut JNIEnv, obj: &JObject) -> String {
+    let class = env.get_object_class(obj).unwrap();
+    let name = env
+        .call_method(class, "getName", "()Ljava/lang/String;", &[])
+        .and_then(|x| x.l())
+        .unwrap();
+    String::from_java(env, &name).unwrap()
+}
+
+#[test]
+fn test_round_trip() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    round_trip(&mut env, true);
+    round_trip(&mut env, -8i8);
+    round_trip(&mut env, -16i16);
+    round_trip(&mut env, i32::MIN);
+    round_trip(&mut env, i64::MAX);
+    round_trip(&mut env, 3.2f32);
+    round_trip(&mut env, 6.4f64);
+    round_trip(&mut env, "hello, 世界".to_string());
+    round_trip(&mut env, vec![0u8, 1, 255]);
+    round_trip(&mut env, Some(42i32));
+    round_trip(&mut env, None::<String>);
+    round_trip(&mut env, vec![Some("a".to_string()), None]);
+    round_trip(&mut env, vec![vec![1u8], vec![]]);
+    round_trip(
+        &mut env,
+        HashMap::from([("a".to_string(), vec![1i64]), ("b".to_string(), vec![])]),
+    );
+}
+
+#[test]
+fn test_java_types() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let cases = [
+        (1i32.into_java(&mut env).unwrap(), "java.lang.Integer"),
+        (false.into_java(&mut env).unwrap(), "java.lang.Boolean"),
+        (vec![1u8].into_java(&mut env).unwrap(), "[B"),
+        (
+            vec![1i32].into_java(&mut env).unwrap(),
+            "java.util.ArrayList",
+        ),
+        (
+            HashMap::<String, String>::new()
+                .into_java(&mut env)
+                .unwrap(),
+            "java.util.HashMap",
+        ),
+    ];
+    for (obj, expected) in cases {
+        assert_eq!(class_name(&mut env, &obj), expected);
+    }
+
+    assert!(None::<i32>.into_java(&mut env).unwrap().is_null());
+    assert!(().into_java(&mut env).unwrap().is_null());
+}
+
+#[test]
+fn test_unexpected_null() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let result = String::from_java(&mut env, &JObject::null());
+    assert!(matches!(result, Err(Error::UnexpectedNull { .. })));
+    let result = Vec::<i32>::from_java(&mut env, &JObject::null());
+    assert!(matches!(result, Err(Error::UnexpectedNull { .. })));
+}
+
+/// A xorshift64* generator, to produce the same cases in each run.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// A UTF-16 code unit, biased to the NUL, the ASCII and the surrogates, which are the
+    /// interesting ones for the modified UTF-8.
+    fn code_unit(&mut self) -> u16 {
+        match self.below(5) {
+            0 => 0,
+            1 => self.below(0x80) as u16,
+            2 => 0xD800 + self.below(0x800) as u16,
+            _ => self.below(0x10000) as u16,
+        }
+    }
+
+    fn code_units(&mut self) -> Vec<u16> {
+        let len = self.below(32);
+        (0..len).map(|_| self.code_unit()).collect()
+    }
+
+    /// A valid string, with the supplementary characters (like the emoji) made from the
+    /// surrogate pairs.
+    fn string(&mut self) -> String {
+        let mut chars = self.code_units();
+        for i in 0..chars.len() {
+            match chars[i] {
+                0xD800..=0xDBFF => {
+                    if i + 1 < chars.len() {
+                        chars[i + 1] = 0xDC00 + (chars[i + 1] & 0x3FF);
+                    } else {
+                        chars[i] = 0x263A;
+                    }
+                }
+                0xDC00..=0xDFFF if i == 0 || !(0xD800..=0xDBFF).contains(&chars[i - 1]) => {
+                    chars[i] = 0x2603;
+                }
+                _ => {}
+            }
+        }
+        String::from_utf16(&chars).unwrap()
+    }
+}
+
+/// Creates a Java string of the UTF-16 `chars` as is, which may not be valid.
+fn new_java_string<'a>(env: &mut JNIEnv<'a>, chars: &[u16]) -> JObject<'a> {
+    let array = env.new_char_array(chars.len() as jint).unwrap();
+    env.set_char_array_region(&array, 0, chars).unwrap();
+    env.new_object("java/lang/String", "([C)V", &[JValue::Object(&array)])
+        .unwrap()
+}
+
+fn java_length(env: &mut JNIEnv, s: &JObject) -> i32 {
+    env.call_method(s, "length", "()I", &[])
+        .and_then(|x| x.i())
+        .unwrap()
+}
+
+#[test]
+fn test_string_special_chars() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    for s in ["", "\0", "a\0b", "\u{1F600}", "\u{10FFFF}", "日本語\0😀"] {
+        let obj = s.to_string().into_java(&mut env).unwrap();
+        assert_eq!(java_length(&mut env, &obj), s.encode_utf16().count() as i32);
+        assert_eq!(String::from_java(&mut env, &obj).unwrap(), s);
+    }
+
+    for chars in [&[0xD800][..], &[0xDC00], &[0x61, 0xDBFF], &[0xDFFF, 0xD800]] {
+        let obj = new_java_string(&mut env, chars);
+        let result = String::from_java(&mut env, &obj);
+        assert!(matches!(result, Err(Error::InvalidUtf16 { .. })));
+    }
+}
+
+#[test]
+fn test_string_round_trip_property() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    let mut rng = Rng(0x9E3779B97F4A7C15);
+
+    for _ in 0..1000 {
+        let _ = env.with_local_frame(8, |env| -> jni::errors::Result<()> {
+            // Rust -> Java -> Rust.
+            let s = rng.string();
+            let obj = s.clone().into_java(env).unwrap();
+            assert_eq!(java_length(env, &obj), s.encode_utf16().count() as i32);
+            assert_eq!(String::from_java(env, &obj).unwrap(), s);
+
+            // Java -> Rust -> Java, for the arbitrary Java strings that may be invalid.
+            let chars = rng.code_units();
+            let obj = new_java_string(env, &chars);
+            match String::from_utf16(&chars) {
+                Ok(expected) => {
+                    let s = String::from_java(env, &obj).unwrap();
+                    assert_eq!(s, expected);
+                    let back = s.into_java(env).unwrap();
+                    let equals = env
+                        .call_method(
+                            &obj,
+                            "equals",
+                            "(Ljava/lang/Object;)Z",
+                            &[JValue::Object(&back)],
+                        )
+                        .and_then(|x| x.z())
+                        .unwrap();
+                    assert!(equals, "{chars:?}");
+                }
+                Err(_) => {
+                    let result = String::from_java(env, &obj);
+                    assert!(
+                        matches!(result, Err(Error::InvalidUtf16 { .. })),
+                        "{chars:?}"
+                    );
+                }
+            }
+            Ok(())
+        });
+    }
+}
@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(test)]
+mod test;
+
 use snafu::prelude::*;
 use snafu::Location;
 
@@ -35,4 +38,117 @@ pub enum Error {
         #[snafu(implicit)]
         loc: Location,
     },
+
+    #[snafu(display("Invalid runtime config: {} at {}", reason, loc))]
+    InvalidRuntimeConfig {
+        reason: String,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Invalid argument: {} at {}", reason, loc))]
+    InvalidArgument {
+        reason: String,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Failed to build runtime: {:?} at {}", error, loc))]
+    BuildRuntime {
+        #[snafu(source)]
+        error: std::io::Error,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Runtime '{}' already exists at {}", name, loc))]
+    RuntimeExists {
+        name: String,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display(
+        "Runtime '{}' not found, it's either not registered or shut down, at {}",
+        name,
+        loc
+    ))]
+    RuntimeNotFound {
+        name: String,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Timed out after {:?} at {}", timeout, loc))]
+    Timeout {
+        timeout: std::time::Duration,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Cannot shut down from a runtime thread at {}", loc))]
+    ShutdownInRuntime {
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Runtime shut down at {}", loc))]
+    RuntimeShutdown {
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Invalid UTF-16 string: {:?} at {}", error, loc))]
+    InvalidUtf16 {
+        #[snafu(source)]
+        error: std::string::FromUtf16Error,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Unexpected null value of Java type '{}' at {}", java_type, loc))]
+    UnexpectedNull {
+        java_type: String,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display(
+        "RustJavaDemo Rust lib is not initialized, call `libInit` first, at {}",
+        loc
+    ))]
+    NotInitialized {
+        #[snafu(implicit)]
+        loc: Location,
+    },
+
+    #[snafu(display("Format error: {:?} at {}", error, loc))]
+    Format {
+        #[snafu(source)]
+        error: std::fmt::Error,
+        #[snafu(implicit)]
+        loc: Location,
+    },
+}
+
+impl Error {
+    /// The location in the Rust source where the error is raised.
+    pub fn location(&self) -> &Location {
+        match self {
+            Error::Jni { loc, .. }
+            | Error::Reqwest { loc, .. }
+            | Error::InvalidRuntimeConfig { loc, .. }
+            | Error::InvalidArgument { loc, .. }
+            | Error::BuildRuntime { loc, .. }
+            | Error::RuntimeExists { loc, .. }
+            | Error::RuntimeNotFound { loc, .. }
+            | Error::Timeout { loc, .. }
+            | Error::ShutdownInRuntime { loc }
+            | Error::RuntimeShutdown { loc }
+            | Error::InvalidUtf16 { loc, .. }
+            | Error::UnexpectedNull { loc, .. }
+            | Error::NotInitialized { loc }
+            | Error::Format { loc, .. } => loc,
+        }
+    }
 }
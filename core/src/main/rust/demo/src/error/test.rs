@@ -0,0 +1,29 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use super::{RuntimeShutdownSnafu, TimeoutSnafu};
+
+#[test]
+fn test_location() {
+    let timeout = Duration::from_secs(1);
+    let err = TimeoutSnafu { timeout }.build();
+    assert_eq!(err.location().file, file!());
+    assert_eq!(err.location().line, line!() - 2);
+
+    let err = RuntimeShutdownSnafu.build();
+    let location = format!("{}:{}:", file!(), line!() - 1);
+    assert!(err.location().to_string().starts_with(&location));
+}
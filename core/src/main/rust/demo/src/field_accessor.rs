@@ -0,0 +1,127 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod test;
+
+use std::marker::PhantomData;
+
+use jni::errors::Result;
+use jni::objects::{GlobalRef, JFieldID, JObject, JStaticFieldID};
+use jni::signature::{JavaType, ReturnType};
+use jni::JNIEnv;
+
+use crate::bindings::JniType;
+use crate::{class_loader, classes};
+
+/// A cached instance field of a Java class, of the type `T`, to be read and written on the
+/// objects of the class (or its subclasses).
+///
+/// Like the [MethodInvoker](crate::method_invoker::MethodInvoker), it's cached in the
+/// `JNI_OnLoad`, so the field can be accessed from the tokio runtime threads, and without a
+/// lookup (or a getter call) for each access.
+pub(crate) struct FieldAccessor<T> {
+    // Keeps the class from being unloaded, which would invalidate the field ID.
+    _class: GlobalRef,
+    field_id: JFieldID,
+    ret: ReturnType,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: JniType> FieldAccessor<T> {
+    /// The JNI signature of the field is derived from `T`.
+    pub(crate) fn try_new(env: &mut JNIEnv, class_name: &str, field_name: &str) -> Result<Self> {
+        let class = class_loader::find_class(env, class_name)?;
+        let sig = classes::resolve_signature(T::SIGNATURE);
+        let field_id = env.get_field_id(&class, field_name, &*sig)?;
+        let ty = T::SIGNATURE.parse::<JavaType>()?;
+        Ok(Self {
+            _class: class,
+            field_id,
+            ret: return_type(&ty),
+            _type: PhantomData,
+        })
+    }
+
+    /// Reads the field of the `obj`.
+    ///
+    /// # Safety
+    ///
+    /// The `obj` must be an instance of the class.
+    pub(crate) unsafe fn get<'l>(&self, env: &mut JNIEnv<'l>, obj: &JObject) -> Result<T::Ret<'l>> {
+        let value = env.get_field_unchecked(obj, self.field_id, self.ret.clone())?;
+        T::from_jvalue(value)
+    }
+
+    /// Writes the field of the `obj`.
+    ///
+    /// # Safety
+    ///
+    /// The `obj` must be an instance of the class, and the `value` (if it's an object) must be an
+    /// instance of the field's class.
+    pub(crate) unsafe fn set(
+        &self,
+        env: &mut JNIEnv,
+        obj: &JObject,
+        value: T::Arg<'_>,
+    ) -> Result<()> {
+        env.set_field_unchecked(obj, self.field_id, T::as_jvalue(&value))
+    }
+}
+
+/// Like the [FieldAccessor], but holds a static field.
+pub(crate) struct StaticFieldAccessor<T> {
+    class: GlobalRef,
+    field_id: JStaticFieldID,
+    ty: JavaType,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: JniType> StaticFieldAccessor<T> {
+    /// The JNI signature of the field is derived from `T`.
+    pub(crate) fn try_new(env: &mut JNIEnv, class_name: &str, field_name: &str) -> Result<Self> {
+        let class = class_loader::find_class(env, class_name)?;
+        let sig = classes::resolve_signature(T::SIGNATURE);
+        let field_id = env.get_static_field_id(&class, field_name, &*sig)?;
+        Ok(Self {
+            class,
+            field_id,
+            ty: T::SIGNATURE.parse()?,
+            _type: PhantomData,
+        })
+    }
+
+    pub(crate) fn get<'l>(&self, env: &mut JNIEnv<'l>) -> Result<T::Ret<'l>> {
+        let value = env.get_static_field_unchecked(&self.class, self.field_id, self.ty.clone())?;
+        T::from_jvalue(value)
+    }
+
+    /// Writes the field.
+    ///
+    /// # Safety
+    ///
+    /// The `value` (if it's an object) must be an instance of the field's class.
+    pub(crate) unsafe fn set(&self, env: &mut JNIEnv, value: T::Arg<'_>) -> Result<()> {
+        env.set_static_field(&self.class, self.field_id, T::as_jvalue(&value))
+    }
+}
+
+fn return_type(ty: &JavaType) -> ReturnType {
+    match ty {
+        JavaType::Primitive(primitive) => ReturnType::Primitive(*primitive),
+        JavaType::Object(_) => ReturnType::Object,
+        JavaType::Array(_) => ReturnType::Array,
+        JavaType::Method(_) => unreachable!("a field cannot be of a method type"),
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jni::objects::JValue;
+use jni::sys::jint;
+
+use super::{FieldAccessor, StaticFieldAccessor};
+use crate::test_util::jvm;
+
+mod awt {
+    use jni::sys::jint;
+
+    crate::java_bindings! {
+        class Point = "java/awt/Point" {
+            field x: jint;
+            field y: jint;
+        }
+
+        class JavaBoolean = "java/lang/Boolean" {
+            static field TRUE: JavaBoolean;
+            fn booleanValue() -> bool;
+        }
+    }
+}
+
+#[test]
+fn test_field_accessor() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let x = FieldAccessor::<jint>::try_new(&mut env, "java/awt/Point", "x").unwrap();
+    let point = env
+        .new_object("java/awt/Point", "(II)V", &[JValue::Int(1), JValue::Int(2)])
+        .unwrap();
+    assert_eq!(unsafe { x.get(&mut env, &point) }.unwrap(), 1);
+
+    unsafe { x.set(&mut env, &point, 42) }.unwrap();
+    assert_eq!(unsafe { x.get(&mut env, &point) }.unwrap(), 42);
+    let result = env
+        .call_method(&point, "getX", "()D", &[])
+        .and_then(|x| x.d())
+        .unwrap();
+    assert_eq!(result, 42.0);
+
+    let max =
+        StaticFieldAccessor::<jint>::try_new(&mut env, "java/lang/Integer", "MAX_VALUE").unwrap();
+    assert_eq!(max.get(&mut env).unwrap(), jint::MAX);
+
+    // The field must be of the type.
+    assert!(FieldAccessor::<bool>::try_new(&mut env, "java/awt/Point", "y").is_err());
+}
+
+#[test]
+fn test_field_bindings() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    awt::init(&mut env).unwrap();
+
+    let point = env
+        .new_object("java/awt/Point", "(II)V", &[JValue::Int(3), JValue::Int(4)])
+        .unwrap();
+    unsafe {
+        awt::Point::y().set(&mut env, &point, -4).unwrap();
+        assert_eq!(awt::Point::x().get(&mut env, &point).unwrap(), 3);
+        assert_eq!(awt::Point::y().get(&mut env, &point).unwrap(), -4);
+    }
+
+    let value = awt::JavaBoolean::TRUE().get(&mut env).unwrap();
+    assert!(unsafe { awt::JavaBoolean::booleanValue(&mut env, &value) }.unwrap());
+}
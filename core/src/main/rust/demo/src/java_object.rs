@@ -0,0 +1,133 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Maps the Rust structs to the Java records or POJOs, by `#[derive(JavaObject)]`:
+//!
+//! ```ignore
+//! #[derive(JavaObject)]
+//! #[java(class = "io/greptime/demo/HttpResponse")]
+//! struct HttpResponse {
+//!     status: i32,
+//!     headers: HashMap<String, Vec<String>>,
+//!     body: Vec<u8>,
+//! }
+//! ```
+//!
+//! The derived struct implements [IntoJava], which creates the Java object by its canonical
+//! constructor, i.e. the one that takes all the fields in the order of their declarations. And
+//! [FromJava], which reads the Java object back field by field. The Rust fields are matched to the
+//! Java fields by their names in camel case, or by `#[java(name = "...")]`. Each field must be a
+//! [JavaField].
+//!
+//! Like the [StaticMethodInvoker](crate::method_invoker::StaticMethodInvoker), the class, the
+//! constructor and the field IDs are cached in [JavaObject::init], which must be called in the
+//! `JNI_OnLoad`.
+//!
+//! [IntoJava]: crate::convert::IntoJava
+//! [FromJava]: crate::convert::FromJava
+//! [JavaField]: crate::convert::JavaField
+
+#[cfg(test)]
+mod test;
+
+pub(crate) use demo_macros::JavaObject;
+use jni::errors::Result;
+use jni::objects::{GlobalRef, JFieldID, JMethodID, JObject, JValueOwned};
+use jni::JNIEnv;
+use snafu::{ensure, ResultExt};
+
+use crate::error::{self, JniSnafu, UnexpectedNullSnafu};
+use crate::{class_loader, classes};
+
+/// A Rust struct that is mapped to a Java class, it's implemented by `#[derive(JavaObject)]`.
+pub(crate) trait JavaObject {
+    /// Looks up the Java class, and caches it for the conversions.
+    fn init(env: &mut JNIEnv) -> Result<()>;
+
+    /// Releases the cached class.
+    fn release();
+}
+
+/// The cached Java class of a [JavaObject].
+pub(crate) struct JavaClass {
+    name: &'static str,
+    class: GlobalRef,
+    constructor: JMethodID,
+    fields: Vec<JFieldID>,
+}
+
+impl JavaClass {
+    /// The `fields` are the names and the type signatures of the Java fields, in the order of the
+    /// canonical constructor's parameters.
+    pub(crate) fn try_new(
+        env: &mut JNIEnv,
+        name: &'static str,
+        fields: &[(&str, &str)],
+    ) -> Result<Self> {
+        let class = class_loader::find_class(env, name)?;
+
+        let sig = format!(
+            "({})V",
+            fields.iter().map(|(_, sig)| *sig).collect::<String>()
+        );
+        let constructor =
+            env.get_method_id(&class, "<init>", &*classes::resolve_signature(&sig))?;
+
+        let fields = fields
+            .iter()
+            .map(|(name, sig)| env.get_field_id(&class, name, &*classes::resolve_signature(sig)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            name,
+            class,
+            constructor,
+            fields,
+        })
+    }
+
+    pub(crate) fn field(&self, i: usize) -> JFieldID {
+        self.fields[i]
+    }
+
+    /// Creates the Java object by the canonical constructor with the `args`, which are converted
+    /// from the fields.
+    pub(crate) fn new_object<'a>(
+        &self,
+        env: &mut JNIEnv<'a>,
+        args: Vec<JValueOwned<'a>>,
+    ) -> error::Result<JObject<'a>> {
+        let jni_args = args.iter().map(|x| x.borrow().as_jni()).collect::<Vec<_>>();
+        // Safety: the constructor is looked up by the signatures of the fields, in the same order
+        // of the `args`.
+        let obj = unsafe { env.new_object_unchecked(&self.class, self.constructor, &jni_args) }
+            .context(JniSnafu)?;
+
+        for arg in args {
+            if let JValueOwned::Object(arg) = arg {
+                env.delete_local_ref(arg).context(JniSnafu)?;
+            }
+        }
+        Ok(obj)
+    }
+
+    pub(crate) fn ensure_not_null(&self, obj: &JObject) -> error::Result<()> {
+        ensure!(
+            !obj.is_null(),
+            UnexpectedNullSnafu {
+                java_type: self.name
+            }
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jni::objects::JObject;
+
+use super::JavaObject;
+use crate::convert::{FromJava, IntoJava};
+use crate::error::Error;
+use crate::test_util::jvm;
+
+#[derive(Debug, Clone, PartialEq, JavaObject)]
+#[java(class = "java/awt/Point")]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, JavaObject)]
+#[java(class = "java/lang/StackTraceElement")]
+struct StackTraceElement {
+    declaring_class: String,
+    #[java(name = "methodName")]
+    method: String,
+    file_name: Option<String>,
+    line_number: i32,
+}
+
+#[test]
+fn test_java_object() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    Point::init(&mut env).unwrap();
+
+    let point = Point { x: 1, y: -2 };
+    let obj = point.clone().into_java(&mut env).unwrap();
+    let s = env
+        .call_method(&obj, "toString", "()Ljava/lang/String;", &[])
+        .and_then(|x| x.l())
+        .unwrap();
+    assert_eq!(
+        String::from_java(&mut env, &s).unwrap(),
+        "java.awt.Point[x=1,y=-2]"
+    );
+    assert_eq!(Point::from_java(&mut env, &obj).unwrap(), point);
+
+    let result = Point::from_java(&mut env, &JObject::null());
+    assert!(matches!(result, Err(Error::UnexpectedNull { .. })));
+}
+
+#[test]
+fn test_java_object_field_names() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    StackTraceElement::init(&mut env).unwrap();
+
+    for file_name in [Some("Foo.java".to_string()), None] {
+        let element = StackTraceElement {
+            declaring_class: "io.greptime.Foo".to_string(),
+            method: "bar".to_string(),
+            file_name,
+            line_number: 42,
+        };
+        let obj = element.clone().into_java(&mut env).unwrap();
+        assert_eq!(
+            StackTraceElement::from_java(&mut env, &obj).unwrap(),
+            element
+        );
+    }
+}
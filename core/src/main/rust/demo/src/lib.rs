@@ -14,23 +14,51 @@
 
 #![feature(once_cell_try)]
 
+mod arrow_ffi;
+mod bindings;
+mod buffer;
+mod cache;
+mod class_loader;
+mod classes;
+mod convert;
 mod error;
+mod field_accessor;
+mod java_object;
 mod logger;
 mod method_invoker;
+mod natives;
+mod publisher;
+mod runtime;
+#[cfg(test)]
+mod test;
+#[cfg(test)]
+mod test_util;
 
 use std::cell::RefCell;
-use std::sync::{Mutex, OnceLock};
-
-use error::{Error, JniSnafu, ReqwestSnafu, Result};
-use futures::TryFutureExt;
-use jni::objects::{AutoLocal, JClass, JObject, JString, JThrowable, JValue};
-use jni::sys::jlong;
-use jni::{sys, JNIEnv, JNIVersion, JavaVM};
-use method_invoker::{ASYNC_REGISTRY_GET_FUTURE, ASYNC_REGISTRY_REGISTER};
-use snafu::ResultExt;
-use tokio::runtime::Runtime;
-
-use crate::logger::{info, CallState, Logger};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use buffer::NativeBuffer;
+use cache::Cache;
+use convert::{FromJava, IntoJava};
+use error::{
+    Error, FormatSnafu, InvalidArgumentSnafu, JniSnafu, NotInitializedSnafu, ReqwestSnafu, Result,
+    RuntimeShutdownSnafu, TimeoutSnafu,
+};
+use futures::{future, stream, TryFutureExt, TryStreamExt};
+use java_object::JavaObject;
+use jni::objects::{GlobalRef, JClass, JLongArray, JObject, JString, JThrowable, JValue};
+use jni::sys::{jint, jlong};
+use jni::{JNIEnv, JNIVersion, JavaVM};
+use runtime::RuntimeConfig;
+use snafu::{IntoError, ResultExt};
+use tokio::task::AbortHandle;
+
+use crate::bindings::{CompletableFuture, Throwable};
+use crate::logger::{info, warn, CallState, Logger};
 
 const JNI_VERSION: JNIVersion = jni::JNIVersion::V1_8;
 
@@ -39,20 +67,23 @@ static INIT_LOCK: Mutex<bool> = Mutex::new(false);
 const LOGGER: Logger = Logger;
 static GLOBAL_LOGGER: OnceLock<Logger> = OnceLock::new();
 
-static RUNTIME: OnceLock<Runtime> = OnceLock::new();
-
 static JAVA_VM: OnceLock<JavaVM> = OnceLock::new();
 
-static CALL_STATE: OnceLock<CallState> = OnceLock::new();
+static CALL_STATE: Cache<CallState> = Cache::new();
 
-thread_local! {
-    static ENV: RefCell<Option<*mut jni::sys::JNIEnv>> = const { RefCell::new(None) };
+/// A running native task, which is to complete the Java `future`.
+struct Task {
+    future: GlobalRef,
+    abort: AbortHandle,
 }
 
-fn runtime() -> &'static Runtime {
-    RUNTIME
-        .get()
-        .expect("Runtime should have been initialized by calling the `libInit` first!")
+static NEXT_TASK_ID: AtomicI64 = AtomicI64::new(1);
+
+/// The running native tasks, by their ids.
+static TASKS: Mutex<BTreeMap<jlong, Task>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    static ENV: RefCell<Option<*mut jni::sys::JNIEnv>> = const { RefCell::new(None) };
 }
 
 fn java_vm() -> &'static JavaVM {
@@ -61,10 +92,9 @@ fn java_vm() -> &'static JavaVM {
         .expect("JavaVM should have been initialized by calling the `libInit` first!")
 }
 
-fn call_state() -> &'static CallState {
-    CALL_STATE
-        .get()
-        .expect("CallState should have been initialized by calling the `libInit` first!")
+/// Returns `None` if the lib is not initialized, or is shut down.
+fn call_state() -> Option<Arc<CallState>> {
+    CALL_STATE.get()
 }
 
 fn jni_env<'a>() -> JNIEnv<'a> {
@@ -76,112 +106,517 @@ fn jni_env<'a>() -> JNIEnv<'a> {
     }
 }
 
-#[no_mangle]
-pub extern "system" fn Java_io_greptime_demo_RustJavaDemo_libInit(
-    mut env: JNIEnv,
-    _class: JClass,
-    runtime_size: sys::jint,
-) {
+extern "system" fn lib_init(mut env: JNIEnv, class: JClass, config: JObject) {
     let mut init = INIT_LOCK.lock().unwrap();
     if *init {
         return;
     }
 
-    if runtime_size < 0 {
-        throw_runtime_exception(&mut env, "`runtime_size` cannot be less than 0".to_string());
+    // The caches are released if the lib was shut down.
+    unwrap_or_throw!(&mut env, method_invoker::init(&mut env));
+    // Must be after the bindings are initialized, and before any runtime is created.
+    unwrap_or_throw!(&mut env, class_loader::init(&mut env, &class));
+
+    let config = unwrap_or_throw!(&mut env, RuntimeConfig::from_java(&mut env, &config));
+    if let Err(e) = config.validate() {
+        throw_exception(&mut env, &e);
         return;
     }
-    let runtime_size = if runtime_size == 0 {
-        num_cpus::get()
-    } else {
-        runtime_size as usize
-    };
 
     let java_vm = JAVA_VM.get_or_try_init(|| env.get_java_vm());
     let java_vm = unwrap_or_throw!(&mut env, java_vm);
 
-    let runtime = RUNTIME.get_or_try_init(|| {
-        tokio::runtime::Builder::new_multi_thread()
-            .worker_threads(runtime_size)
-            .on_thread_start(move || {
-                ENV.with(|cell| {
-                    let env =
-                        unsafe { java_vm.attach_current_thread_as_daemon() }.unwrap_or_else(|e| {
-                            panic!("Failed to attach tokio's threads to JVM, err: {e:?}")
-                        });
-                    *cell.borrow_mut() = Some(env.get_raw());
-                })
-            })
-            .enable_all()
-            .build()
-    });
-    unwrap_or_throw!(&mut env, runtime);
-
     let call_state = CALL_STATE.get_or_try_init(|| CallState::try_new(&mut env));
     unwrap_or_throw!(&mut env, call_state);
 
+    // The runtime is the last to be created, nothing after it can fail. So a failed `libInit`
+    // leaves no runtime behind, and can simply be retried.
+    unwrap_or_throw!(
+        &mut env,
+        runtime::init(&mut env, java_vm, runtime::DEFAULT_RUNTIME, &config)
+    );
+
     GLOBAL_LOGGER.get_or_init(|| {
         log::set_logger(&LOGGER).expect("unable to set `Logger` as the global logger");
-        log::set_max_level(log::LevelFilter::Trace);
         LOGGER
     });
+    // The global logger can only be set once, it's turned off when the lib is shut down.
+    log::set_max_level(log::LevelFilter::Trace);
 
     *init = true;
 
     info!(
-        "RustJavaDemo Rust lib is initialized with runtime size {}",
-        runtime_size
+        "RustJavaDemo Rust lib is initialized with runtime config {:?}",
+        config
     );
 }
 
-#[no_mangle]
-pub extern "system" fn Java_io_greptime_demo_RustJavaDemo_nativeHello<'a>(
+extern "system" fn register_runtime(
+    mut env: JNIEnv,
+    _class: JClass,
+    name: JString,
+    config: JObject,
+) {
+    let init = INIT_LOCK.lock().unwrap();
+    if !*init {
+        throw_exception(&mut env, &NotInitializedSnafu.build());
+        return;
+    }
+
+    let name = unwrap_or_throw!(&mut env, String::from_java(&mut env, &name));
+    let config = unwrap_or_throw!(&mut env, RuntimeConfig::from_java(&mut env, &config));
+    if let Err(e) = config.validate() {
+        throw_exception(&mut env, &e);
+        return;
+    }
+
+    if let Err(e) = runtime::init(&mut env, java_vm(), &name, &config) {
+        throw_exception(&mut env, &e);
+        return;
+    }
+
+    info!(
+        "Runtime '{}' is registered with runtime config {:?}",
+        name, config
+    );
+}
+
+extern "system" fn runtime_metrics<'a>(
     mut env: JNIEnv<'a>,
     _class: JClass,
+    runtime: JString<'a>,
+) -> JObject<'a> {
+    unwrap_or_throw!(
+        &mut env,
+        new_runtime_metrics(&mut env, runtime),
+        JObject::null()
+    )
+}
+
+fn new_runtime_metrics<'a>(env: &mut JNIEnv<'a>, runtime: JString<'a>) -> Result<JObject<'a>> {
+    let runtime = runtime_name(env, &runtime)?;
+    let metrics = runtime::metrics(&runtime)?;
+
+    let worker_local_queue_depths = metrics
+        .worker_local_queue_depths
+        .iter()
+        .map(|x| *x as jlong)
+        .collect::<Vec<_>>();
+    let worker_busy_durations = metrics
+        .worker_busy_durations
+        .iter()
+        .map(|x| x.as_nanos() as jlong)
+        .collect::<Vec<_>>();
+    let worker_park_counts = metrics
+        .worker_park_counts
+        .iter()
+        .map(|x| *x as jlong)
+        .collect::<Vec<_>>();
+
+    let worker_local_queue_depths = new_long_array(env, &worker_local_queue_depths)?;
+    let worker_busy_durations = new_long_array(env, &worker_busy_durations)?;
+    let worker_park_counts = new_long_array(env, &worker_park_counts)?;
+    let class =
+        class_loader::find_class(env, "io/greptime/demo/RuntimeMetrics").context(JniSnafu)?;
+    env.new_object(
+        &class,
+        "(IJJ[J[J[JIIJ)V",
+        &[
+            JValue::Int(metrics.workers as jint),
+            JValue::Long(metrics.alive_tasks as jlong),
+            JValue::Long(metrics.global_queue_depth as jlong),
+            JValue::Object(&worker_local_queue_depths),
+            JValue::Object(&worker_busy_durations),
+            JValue::Object(&worker_park_counts),
+            JValue::Int(metrics.blocking_threads as jint),
+            JValue::Int(metrics.idle_blocking_threads as jint),
+            JValue::Long(metrics.blocking_queue_depth as jlong),
+        ],
+    )
+    .context(JniSnafu)
+}
+
+fn new_long_array<'a>(env: &mut JNIEnv<'a>, values: &[jlong]) -> Result<JLongArray<'a>> {
+    let array = env.new_long_array(values.len() as jint).context(JniSnafu)?;
+    env.set_long_array_region(&array, 0, values)
+        .context(JniSnafu)?;
+    Ok(array)
+}
+
+extern "system" fn lib_shutdown(mut env: JNIEnv, _class: JClass, timeout_millis: jlong) {
+    let mut init = INIT_LOCK.lock().unwrap();
+    if !*init {
+        return;
+    }
+
+    if timeout_millis < 0 {
+        let err = InvalidArgumentSnafu {
+            reason: "`timeout_millis` cannot be less than 0",
+        }
+        .build();
+        throw_exception(&mut env, &err);
+        return;
+    }
+
+    unwrap_or_throw!(
+        &mut env,
+        shutdown(&mut env, Duration::from_millis(timeout_millis as u64))
+    );
+    *init = false;
+}
+
+/// Shuts down all the runtimes and fails the unfinished tasks, then releases the caches.
+fn shutdown(env: &mut JNIEnv, timeout: Duration) -> Result<()> {
+    if !runtime::shutdown(timeout)? {
+        warn!("RustJavaDemo Rust lib is shut down with some tasks unfinished");
+    }
+
+    // The futures of the dropped tasks would never be completed, fail them all.
+    let tasks = std::mem::take(&mut *TASKS.lock().unwrap());
+    for task in tasks.into_values() {
+        complete_future(env, &task.future, RuntimeShutdownSnafu.fail::<()>());
+    }
+    publisher::fail_all(env);
+
+    info!("RustJavaDemo Rust lib is shut down");
+    release_caches();
+    Ok(())
+}
+
+/// Releases the cached Java classes and objects (like the loggers), which would otherwise keep
+/// the class loader of `RustJavaDemo` from being garbage collected, and the `JNI_OnUnload` would
+/// never be called. They are cached again in the `libInit`.
+fn release_caches() {
+    // There's no way to unset the global logger, make it a no-op instead.
+    log::set_max_level(log::LevelFilter::Off);
+    CALL_STATE.release();
+    method_invoker::release();
+    class_loader::release();
+}
+
+/// Called in the `JNI_OnUnload`, shuts the lib down in case the `libShutdown` is not called. And
+/// releases the caches that are kept after the shutdown as well.
+fn unload(env: &mut JNIEnv) {
+    let mut init = INIT_LOCK.lock().unwrap();
+    if *init {
+        // It never fails, the `JNI_OnUnload` is not called from a runtime thread.
+        let _ = shutdown(env, Duration::ZERO);
+        *init = false;
+    } else {
+        release_caches();
+    }
+    method_invoker::release_all();
+}
+
+extern "system" fn native_hello<'a>(
+    mut env: JNIEnv<'a>,
+    _class: JClass,
+    future: JObject<'a>,
     url: JString<'a>,
+    runtime: JString<'a>,
+    timeout_millis: jlong,
 ) -> jlong {
-    unwrap_or_throw!(&mut env, hello(&mut env, url), 0)
+    if timeout_millis < 0 {
+        let err = InvalidArgumentSnafu {
+            reason: "`timeout_millis` cannot be less than 0",
+        }
+        .build();
+        throw_exception(&mut env, &err);
+        return 0;
+    }
+    let timeout = timeout(timeout_millis);
+
+    unwrap_or_throw!(&mut env, hello(&mut env, future, url, runtime, timeout), 0)
 }
 
-fn hello<'a>(env: &mut JNIEnv<'a>, url: JString<'a>) -> Result<jlong> {
-    let url: String = env.get_string(&url).context(JniSnafu)?.into();
+/// Converts the non-negative `timeout_millis` from Java, where 0 means no timeout. So does one that
+/// is too long to be a deadline, like the `Long.MAX_VALUE` that `TimeUnit::toMillis` saturates to.
+fn timeout(timeout_millis: jlong) -> Option<Duration> {
+    let timeout = Duration::from_millis(timeout_millis as u64);
+    (timeout_millis > 0 && Instant::now().checked_add(timeout).is_some()).then_some(timeout)
+}
 
-    let future_id = register(env).context(JniSnafu)?;
-    runtime().spawn(async move {
+fn hello<'a>(
+    env: &mut JNIEnv<'a>,
+    future: JObject<'a>,
+    url: JString<'a>,
+    runtime: JString<'a>,
+    timeout: Option<Duration>,
+) -> Result<jlong> {
+    let url = String::from_java(env, &url)?;
+    let runtime = runtime_name(env, &runtime)?;
+
+    let future = env.new_global_ref(future).context(JniSnafu)?;
+    let task_future = future.clone();
+    let task_id = spawn_task(env, &runtime, future, timeout, async move {
+        // The error statuses are failures as well, so they are thrown with the status.
         let result = reqwest::get(url)
+            .and_then(|resp| future::ready(resp.error_for_status()))
             .and_then(|resp| resp.text())
             .await
             .context(ReqwestSnafu);
+        complete_future(&mut jni_env(), &task_future, result);
+    });
+    Ok(task_id)
+}
 
-        let env = &mut jni_env();
-        let result = result.and_then(|x| {
-            env.new_string(x)
-                .context(JniSnafu)
-                .map(|x| AutoLocal::new(x.into(), env))
-        });
-        complete_future(future_id, result);
+extern "system" fn native_hello_bytes<'a>(
+    mut env: JNIEnv<'a>,
+    _class: JClass,
+    future: JObject<'a>,
+    url: JString<'a>,
+) -> jlong {
+    unwrap_or_throw!(&mut env, hello_bytes(&mut env, future, url), 0)
+}
+
+/// Like [hello], but the body is handed to Java as is in a [NativeBuffer], rather than being
+/// copied and converted to a Java string.
+fn hello_bytes<'a>(env: &mut JNIEnv<'a>, future: JObject<'a>, url: JString<'a>) -> Result<jlong> {
+    let url = String::from_java(env, &url)?;
+
+    let future = env.new_global_ref(future).context(JniSnafu)?;
+    let task_future = future.clone();
+    let task_id = spawn_task(env, runtime::DEFAULT_RUNTIME, future, None, async move {
+        let result = reqwest::get(url)
+            .and_then(|resp| future::ready(resp.error_for_status()))
+            .and_then(|resp| resp.bytes())
+            .await
+            .map(|x| NativeBuffer(x.into()))
+            .context(ReqwestSnafu);
+        complete_future(&mut jni_env(), &task_future, result);
+    });
+    Ok(task_id)
+}
+
+extern "system" fn native_range<'a>(
+    mut env: JNIEnv<'a>,
+    _class: JClass,
+    future: JObject<'a>,
+    stream: jlong,
+    start: jlong,
+    end: jlong,
+    batch_size: jlong,
+) -> jlong {
+    unwrap_or_throw!(
+        &mut env,
+        range(&mut env, future, stream, start, end, batch_size),
+        0
+    )
+}
+
+/// Exports the numbers in `start..end` as Arrow record batches, to the `ArrowArrayStream` struct at
+/// the address `stream`. The Java future is completed when the stream is ready to be imported.
+fn range<'a>(
+    env: &mut JNIEnv<'a>,
+    future: JObject<'a>,
+    stream: jlong,
+    start: jlong,
+    end: jlong,
+    batch_size: jlong,
+) -> Result<jlong> {
+    let batch_size = arrow_ffi::check_range(start, end, batch_size)?;
+
+    let future = env.new_global_ref(future).context(JniSnafu)?;
+    let task_future = future.clone();
+    let task_id = spawn_task(env, runtime::DEFAULT_RUNTIME, future, None, async move {
+        let reader = arrow_ffi::range_reader(start, end, batch_size);
+        // Safety: the Java side doesn't touch the struct until the future is completed.
+        unsafe { arrow_ffi::export_reader(Box::new(reader), stream as *mut _) };
+        complete_future(&mut jni_env(), &task_future, Ok(()));
+    });
+    Ok(task_id)
+}
+
+/// The response of [fetch], it's the Java record `io.greptime.demo.HttpResponse`.
+#[derive(JavaObject)]
+#[java(class = "io/greptime/demo/HttpResponse")]
+struct HttpResponse {
+    status: i32,
+    headers: HashMap<String, Vec<String>>,
+    body: Vec<u8>,
+}
+
+extern "system" fn native_fetch<'a>(
+    mut env: JNIEnv<'a>,
+    _class: JClass,
+    future: JObject<'a>,
+    url: JString<'a>,
+) -> jlong {
+    unwrap_or_throw!(&mut env, fetch(&mut env, future, url), 0)
+}
+
+/// Like [hello], but the Java future is completed with the status and the headers of the response
+/// as well.
+fn fetch<'a>(env: &mut JNIEnv<'a>, future: JObject<'a>, url: JString<'a>) -> Result<jlong> {
+    let url = String::from_java(env, &url)?;
+
+    let future = env.new_global_ref(future).context(JniSnafu)?;
+    let task_future = future.clone();
+    let task_id = spawn_task(env, runtime::DEFAULT_RUNTIME, future, None, async move {
+        let result = async {
+            let resp = reqwest::get(url).await?;
+
+            let status = resp.status().as_u16() as i32;
+            let mut headers = HashMap::<_, Vec<_>>::new();
+            for (name, value) in resp.headers() {
+                headers
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(String::from_utf8_lossy(value.as_bytes()).into_owned());
+            }
+            let body = resp.bytes().await?.to_vec();
+
+            Ok::<_, reqwest::Error>(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        }
+        .await
+        .context(ReqwestSnafu);
+        complete_future(&mut jni_env(), &task_future, result);
+    });
+    Ok(task_id)
+}
+
+extern "system" fn native_hello_stream<'a>(
+    mut env: JNIEnv<'a>,
+    _class: JClass,
+    publisher: JObject<'a>,
+    url: JString<'a>,
+    runtime: JString<'a>,
+) -> jlong {
+    unwrap_or_throw!(&mut env, hello_stream(&mut env, publisher, url, runtime), 0)
+}
+
+/// Like [hello], but streams the body in chunks as they arrive, instead of buffering all of it.
+fn hello_stream<'a>(
+    env: &mut JNIEnv<'a>,
+    publisher: JObject<'a>,
+    url: JString<'a>,
+    runtime: JString<'a>,
+) -> Result<jlong> {
+    let url = String::from_java(env, &url)?;
+    let runtime = runtime_name(env, &runtime)?;
+
+    let chunks =
+        stream::once(reqwest::get(url).and_then(|resp| future::ready(resp.error_for_status())))
+            .map_ok(|resp| {
+                stream::try_unfold(resp, |mut resp| async move {
+                    let chunk = resp.chunk().await?;
+                    Ok::<_, reqwest::Error>(chunk.map(|chunk| (chunk, resp)))
+                })
+            })
+            .try_flatten()
+            .map_err(|error| ReqwestSnafu.into_error(error));
+
+    let publisher = env.new_global_ref(publisher).context(JniSnafu)?;
+    publisher::spawn_publisher(&runtime, publisher, chunks, |env, chunk| {
+        env.byte_array_from_slice(&chunk)
+            .map(JObject::from)
+            .context(JniSnafu)
+    })
+}
+
+extern "system" fn native_echo<'a>(
+    mut env: JNIEnv<'a>,
+    _class: JClass,
+    future: JObject<'a>,
+    value: JString<'a>,
+) -> jlong {
+    unwrap_or_throw!(&mut env, echo(&mut env, future, value), 0)
+}
+
+/// Completes the future with the `value` in the default runtime. It does nothing else, so it's
+/// mostly useful to measure the overhead of the async bridge itself.
+fn echo<'a>(env: &mut JNIEnv<'a>, future: JObject<'a>, value: JString<'a>) -> Result<jlong> {
+    let value = env.new_global_ref(value).context(JniSnafu)?;
+
+    let future = env.new_global_ref(future).context(JniSnafu)?;
+    let task_future = future.clone();
+    let task_id = spawn_task(env, runtime::DEFAULT_RUNTIME, future, None, async move {
+        complete_future(&mut jni_env(), &task_future, Ok(value));
     });
-    Ok(future_id)
+    Ok(task_id)
+}
+
+/// Spawns the `task` that completes the Java `future` in the `runtime`. Returns the id of the task,
+/// by which the task can be aborted when the Java future is cancelled.
+///
+/// If the `timeout` is set, the task is dropped when it's not done in time, and the Java future is
+/// completed with a timeout error.
+fn spawn_task<F>(
+    env: &mut JNIEnv,
+    runtime: &str,
+    future: GlobalRef,
+    timeout: Option<Duration>,
+    task: F,
+) -> jlong
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let task_id = NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed);
+
+    // Hold the lock until the task is inserted, in case the task is done before that.
+    let mut tasks = TASKS.lock().unwrap();
+    let task_future = future.clone();
+    let spawned = runtime::spawn(runtime, async move {
+        match timeout {
+            Some(timeout) => {
+                if tokio::time::timeout(timeout, task).await.is_err() {
+                    let result = TimeoutSnafu { timeout }.fail::<()>();
+                    complete_future(&mut jni_env(), &task_future, result);
+                }
+            }
+            None => task.await,
+        }
+        TASKS.lock().unwrap().remove(&task_id);
+    });
+    match spawned {
+        Ok(handle) => {
+            tasks.insert(
+                task_id,
+                Task {
+                    future,
+                    abort: handle.abort_handle(),
+                },
+            );
+        }
+        Err(e) => {
+            drop(tasks);
+            complete_future(env, &future, Err::<(), _>(e));
+        }
+    }
+    task_id
+}
+
+extern "system" fn cancel(_env: JNIEnv, _class: JClass, task_id: jlong) {
+    // Aborting the task drops it, as well as the resources it holds, like the HTTP connections.
+    if let Some(task) = TASKS.lock().unwrap().remove(&task_id) {
+        task.abort.abort();
+    }
+}
+
+/// Gets the name of the runtime to run the native operation in, a `null` means the default one.
+fn runtime_name(env: &mut JNIEnv, name: &JString) -> Result<String> {
+    let name = Option::<String>::from_java(env, name)?;
+    Ok(name.unwrap_or_else(|| runtime::DEFAULT_RUNTIME.to_string()))
 }
 
 // This future interaction between Java and Rust idea is borrow from OpenDAL, hats off to it!
-fn complete_future<'a>(id: jlong, result: Result<AutoLocal<'a, JObject<'a>>>) {
-    let env = &mut jni_env();
+//
+// The Java side creates the `CompletableFuture` and passes it down, the Rust side holds it as a
+// global ref and completes it directly when the async operation is done.
+fn complete_future<T: IntoJava>(env: &mut JNIEnv, future: &JObject, result: Result<T>) {
     let _ = env.with_local_frame(16, |env| -> jni::errors::Result<()> {
-        let future = get_future(env, id)
-            .unwrap_or_else(|e| panic!("Failed to get Java future by id '{}', error: {:?}", id, e));
-        match result {
-            Ok(result) => env
-                .call_method(
-                    future,
-                    "complete",
-                    "(Ljava/lang/Object;)Z",
-                    &[JValue::Object(&result)],
-                )
+        match result.and_then(|x| x.into_java(env)) {
+            // Safety: the `future` is passed down as a `CompletableFuture` by the native methods,
+            // and the result can be any object.
+            Ok(result) => unsafe { CompletableFuture::complete(env, future, &result) }
                 .unwrap_or_else(|e| {
                     panic!(
-                        "Failed to complete Java future '{}' with result '{:?}', error: {:?}",
-                        id, result, e
+                        "Failed to complete Java future with result '{:?}', error: {:?}",
+                        result, e
                     )
                 }),
             Err(err) => {
@@ -191,108 +626,132 @@ fn complete_future<'a>(id: jlong, result: Result<AutoLocal<'a, JObject<'a>>>) {
                         err, e
                     )
                 });
-                env.call_method(
-                    future,
-                    "completeExceptionally",
-                    "(Ljava/lang/Throwable;)Z",
-                    &[JValue::Object(&ex)],
-                )
-                .unwrap_or_else(|e| {
-                    panic!(
-                        "Failed to complete Java future '{}' with error '{:?}', error: {:?}",
-                        id, err, e
-                    )
-                })
+                // Safety: same as above, and the `ex` is a `Throwable`.
+                unsafe { CompletableFuture::completeExceptionally(env, future, &ex) }
+                    .unwrap_or_else(|e| {
+                        panic!(
+                            "Failed to complete Java future with error '{:?}', error: {:?}",
+                            err, e
+                        )
+                    })
             }
         };
         Ok(())
     });
 }
 
-fn get_future<'local>(env: &mut JNIEnv<'local>, id: jlong) -> jni::errors::Result<JObject<'local>> {
-    invoke_static_method!(env, ASYNC_REGISTRY_GET_FUTURE, &[JValue::Long(id).as_jni()])?.l()
-}
-
+/// Creates the Java exception of the `err`'s kind: a `RustJniException`, a `RustHttpException` or
+/// a `RustTimeoutException`, and a `RustException` for the others. They carry the `err`'s source
+/// location, and the extra data of the kind (like the HTTP status code).
 fn make_exception<'local>(
     env: &mut JNIEnv<'local>,
     err: &Error,
 ) -> jni::errors::Result<JThrowable<'local>> {
-    let exception = env.find_class("java/lang/RuntimeException")?;
-    let err = env.new_string(format!("{:?}", err))?;
-    env.new_object(exception, "(Ljava/lang/String;)V", &[JValue::Object(&err)])
-        .map(JThrowable::from)
-}
-
-fn register(env: &mut JNIEnv) -> jni::errors::Result<jlong> {
-    invoke_static_method!(env, ASYNC_REGISTRY_REGISTER, &[])?.j()
-}
-
-fn throw_runtime_exception(env: &mut JNIEnv, msg: String) {
-    // There could be a pending exception that is thrown by calling into the Java side.
-    // If we simply use the `msg` as the exception message, we would lose the original Java exception's details,
-    // resulting in a vague "JavaException" error message.
-    // It would be nice if https://github.com/jni-rs/jni-rs/pull/498/ is merged.
-    let msg = if let Some(ex) = env.exception_occurred() {
-        env.exception_clear();
-
-        let class = env
-            .get_object_class(&ex)
-            .and_then(|x| env.call_method(x, "getName", "()Ljava/lang/String;", &[]))
-            .and_then(|x| x.l())
-            .unwrap_or_else(|e| {
-                panic!(
-                    "Failed to get class name for exception: '{:?}', error: '{}'",
-                    *ex, e
-                )
-            })
-            .into();
-        let class = env.get_string(&class).unwrap_or_else(|e| {
-            panic!(
-                "Failed to get string: '{:?}' from env, error: '{}'",
-                *class, e
+    let class =
+        class_loader::find_class(env, &format!("io/greptime/demo/{}", exception_class(err)))?;
+
+    let url;
+    let (extra_sig, extra_args) = match err {
+        Error::Reqwest { error, .. } => {
+            url = match error.url() {
+                Some(url) => JObject::from(env.new_string(url.as_str())?),
+                None => JObject::null(),
+            };
+            // No status if the request failed before a response is received.
+            let status = error.status().map_or(0, |x| x.as_u16() as jint);
+            (
+                "ILjava/lang/String;",
+                vec![JValue::Int(status), JValue::Object(&url)],
             )
-        });
+        }
+        Error::Timeout { timeout, .. } => ("J", vec![JValue::Long(timeout.as_millis() as jlong)]),
+        _ => ("", vec![]),
+    };
 
-        let message = env
-            .call_method(&ex, "getMessage", "()Ljava/lang/String;", &[])
-            .and_then(|x| x.l())
-            .unwrap_or_else(|e| {
-                panic!(
-                    "Failed to get message for exception: '{:?}', error: '{}'",
-                    *ex, e
-                )
-            })
-            .into();
-        let message = env.get_string(&message).unwrap_or_else(|e| {
+    let message = env.new_string(err.to_string())?;
+    let location = env.new_string(err.location().to_string())?;
+    let mut args = vec![JValue::Object(&message), JValue::Object(&location)];
+    args.extend(extra_args);
+    env.new_object(
+        &class,
+        format!("(Ljava/lang/String;Ljava/lang/String;{extra_sig})V"),
+        &args,
+    )
+    .map(JThrowable::from)
+}
+
+/// The simple name of the Java exception class of the `err`'s kind, see [make_exception].
+fn exception_class(err: &Error) -> &'static str {
+    match err {
+        Error::Jni { .. } => "RustJniException",
+        Error::Reqwest { .. } => "RustHttpException",
+        Error::Timeout { .. } => "RustTimeoutException",
+        _ => "RustException",
+    }
+}
+
+/// Throws the `err` as the Java exception of its kind, see [make_exception].
+fn throw_exception(env: &mut JNIEnv, err: &Error) {
+    // There could be a pending exception that is thrown by calling into the Java side, it's kept
+    // as the cause, so its details are not lost.
+    let cause = env.exception_occurred();
+    env.exception_clear();
+
+    let ex = make_exception(env, err).unwrap_or_else(|e| {
+        panic!(
+            "Failed to create Java exception for error '{:?}', error: {:?}",
+            err, e
+        )
+    });
+    if let Some(cause) = cause {
+        // Safety: both of them are `Throwable`s.
+        unsafe { Throwable::initCause(env, &ex, &cause) }.unwrap_or_else(|e| {
             panic!(
-                "Failed to get string: '{:?}' from env, error: '{}'",
-                *message, e
+                "Failed to set the cause of Java exception for error '{:?}', error: {:?}",
+                err, e
             )
         });
+    }
+    env.throw(ex)
+        .unwrap_or_else(|e| panic!("Failed to throw error '{err:?}' as Java exception: {e:?}"));
+}
 
-        format!(
-            "{}. Java exception occurred: {}: {}",
-            msg,
-            class.to_str(),
-            message.to_str()
-        )
-    } else {
-        msg
-    };
+/// An error that is thrown as a Java exception by the [unwrap_or_throw].
+trait ThrowError {
+    fn throw(self, env: &mut JNIEnv);
+}
 
-    env.throw_new("java/lang/RuntimeException", &msg)
-        .unwrap_or_else(|e| panic!("Failed to throw error '{msg}' as Java exception: {e:?}"));
+impl ThrowError for Error {
+    fn throw(self, env: &mut JNIEnv) {
+        throw_exception(env, &self)
+    }
+}
+
+impl ThrowError for jni::errors::Error {
+    // The location of the `Error::Jni` is where the `unwrap_or_throw` is called.
+    #[track_caller]
+    fn throw(self, env: &mut JNIEnv) {
+        throw_exception(env, &JniSnafu.into_error(self))
+    }
+}
+
+impl ThrowError for std::fmt::Error {
+    // Same as above.
+    #[track_caller]
+    fn throw(self, env: &mut JNIEnv) {
+        throw_exception(env, &FormatSnafu.into_error(self))
+    }
 }
 
 /// Throws a Java exception if the result is an error.
-/// The error will be formatted to string as the exception's message.
+/// The [Error]s are thrown as the exceptions of their kinds, see [make_exception].
 #[macro_export]
 macro_rules! unwrap_or_throw {
     ($env:expr, $res:expr $(, $ret:expr)?) => {
         match $res {
             Ok(x) => x,
             Err(e) => {
-                $crate::throw_runtime_exception($env, format!("{e:?}"));
+                $crate::ThrowError::throw(e, $env);
                 return $($ret)?;
             }
         }
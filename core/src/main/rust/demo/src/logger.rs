@@ -25,8 +25,7 @@ use jni::signature::{Primitive, ReturnType};
 use jni::JNIEnv;
 use log::{Level, Log};
 
-use crate::method_invoker::LOGGER;
-use crate::{call_state, invoke_static_method, java_vm, unwrap_or_throw};
+use crate::{bindings, call_state, class_loader, java_vm, unwrap_or_throw};
 
 struct LogMethods {
     error: JMethodID,
@@ -58,13 +57,13 @@ pub(crate) struct CallState {
 
 impl CallState {
     pub(crate) fn try_new(env: &mut JNIEnv) -> Result<Self> {
-        let class_name = "io/greptime/demo/utils/Logger";
+        let class = &class_loader::find_class(env, "io/greptime/demo/utils/Logger")?;
         let methods = LogMethods {
-            error: env.get_method_id(class_name, "error", "(Ljava/lang/String;)V")?,
-            warn: env.get_method_id(class_name, "warn", "(Ljava/lang/String;)V")?,
-            info: env.get_method_id(class_name, "info", "(Ljava/lang/String;)V")?,
-            debug: env.get_method_id(class_name, "debug", "(Ljava/lang/String;)V")?,
-            trace: env.get_method_id(class_name, "trace", "(Ljava/lang/String;)V")?,
+            error: env.get_method_id(class, "error", "(Ljava/lang/String;)V")?,
+            warn: env.get_method_id(class, "warn", "(Ljava/lang/String;)V")?,
+            info: env.get_method_id(class, "info", "(Ljava/lang/String;)V")?,
+            debug: env.get_method_id(class, "debug", "(Ljava/lang/String;)V")?,
+            trace: env.get_method_id(class, "trace", "(Ljava/lang/String;)V")?,
         };
         Ok(Self {
             loggers: Mutex::new(HashMap::new()),
@@ -88,9 +87,10 @@ impl CallState {
     }
 
     fn init_logger(&self, env: &mut JNIEnv, name: &str) -> Result<GlobalRef> {
-        let name = JValue::from(&env.new_string(name)?).as_jni();
-        let logger = invoke_static_method!(env, LOGGER, &[name])?;
-        env.new_global_ref(logger.l()?).map_err(Into::into)
+        let name = env.new_string(name)?;
+        // Safety: the `name` is a `String`.
+        let logger = unsafe { bindings::Logger::getLogger(env, &name) }?;
+        env.new_global_ref(logger).map_err(Into::into)
     }
 }
 
@@ -108,10 +108,13 @@ impl Log for Logger {
             return;
         }
 
+        // The lib is shut down.
+        let Some(call_state) = call_state() else {
+            return;
+        };
         let env = &mut java_vm()
             .attach_current_thread_permanently()
             .expect("unable to attach current thread to JavaVM");
-        let call_state = call_state();
 
         let msg = unwrap_or_throw!(env, format_msg(record));
 
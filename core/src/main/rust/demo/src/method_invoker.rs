@@ -16,19 +16,21 @@
 mod test;
 
 use std::ffi::c_void;
-use std::sync::OnceLock;
+use std::panic::{self, AssertUnwindSafe};
 
-use jni::errors::Result;
-use jni::objects::{GlobalRef, JStaticMethodID, JValueOwned};
-use jni::signature::{Primitive, ReturnType};
-use jni::sys::{jint, jvalue};
+use jni::errors::{Error, Result};
+use jni::objects::{
+    GlobalRef, JMethodID, JObject, JStaticMethodID, JThrowable, JValue, JValueOwned,
+};
+use jni::signature::{JavaType, Primitive, TypeSignature};
+use jni::sys::{jint, jvalue, JNI_ERR};
 use jni::{JNIEnv, JavaVM};
+use snafu::ResultExt;
 
-use crate::JNI_VERSION;
-
-pub(crate) static LOGGER: OnceLock<StaticMethodInvoker> = OnceLock::new();
-pub(crate) static ASYNC_REGISTRY_REGISTER: OnceLock<StaticMethodInvoker> = OnceLock::new();
-pub(crate) static ASYNC_REGISTRY_GET_FUTURE: OnceLock<StaticMethodInvoker> = OnceLock::new();
+use crate::error::JniSnafu;
+use crate::java_object::JavaObject;
+use crate::logger::error;
+use crate::{bindings, class_loader, classes, natives, HttpResponse, JNI_VERSION};
 
 /// A struct that holds a the static method of the Java side, to be invoked later.
 ///
@@ -54,88 +56,214 @@ pub(crate) static ASYNC_REGISTRY_GET_FUTURE: OnceLock<StaticMethodInvoker> = Onc
 pub(crate) struct StaticMethodInvoker {
     class: GlobalRef,
     method_id: JStaticMethodID,
-    ret: ReturnType,
+    sig: TypeSignature,
 }
 
 impl StaticMethodInvoker {
-    fn try_new(
+    /// The return type of the method is derived from its JNI signature `sig`.
+    pub(crate) fn try_new(
         env: &mut JNIEnv,
         class_name: &str,
         method_name: &str,
         sig: &str,
-        ret: ReturnType,
     ) -> Result<Self> {
-        let class = env.find_class(class_name)?;
-        let class = env.new_global_ref(class)?;
-        let method_id = env.get_static_method_id(class_name, method_name, sig)?;
+        let class = class_loader::find_class(env, class_name)?;
+        let sig = classes::resolve_signature(sig);
+        let method_id = env.get_static_method_id(&class, method_name, &*sig)?;
         Ok(Self {
             class,
             method_id,
-            ret,
+            sig: TypeSignature::from_str(&sig)?,
         })
     }
 
-    pub(crate) unsafe fn invoke<'local>(
+    /// Invokes the method with the `args`. They are converted on the stack, so the invocation
+    /// doesn't allocate.
+    ///
+    /// # Safety
+    ///
+    /// The `args` must match the signature of the method. In debug builds, the number of them
+    /// and their primitive kinds are checked, an [Error::InvalidArgList] is returned on mismatch.
+    /// The classes of the object arguments are never checked.
+    pub(crate) unsafe fn invoke<'local, const N: usize>(
         &self,
         env: &mut JNIEnv<'local>,
-        args: &[jvalue],
+        args: &[JValue; N],
     ) -> Result<JValueOwned<'local>> {
-        env.call_static_method_unchecked(&self.class, self.method_id, self.ret.clone(), args)
+        if cfg!(debug_assertions) {
+            check_args(&self.sig, args)?;
+        }
+        let args: [jvalue; N] = args.each_ref().map(|x| x.as_jni());
+        env.call_static_method_unchecked(&self.class, self.method_id, self.sig.ret.clone(), &args)
     }
 }
 
-#[macro_export]
-macro_rules! invoke_static_method {
-    ($env: ident, $invoker: ident, $args: expr) => {{
-        let invoker = $invoker.get().unwrap_or_else(|| {
-            panic!(
-                "StaticMethodInvoker '{}' must to be initialized in 'JNI_OnLoad'!",
-                stringify!($invoker)
-            );
+/// Like the [StaticMethodInvoker], but holds an instance method, to be invoked on the objects of
+/// the class (or its subclasses).
+pub(crate) struct MethodInvoker {
+    // Keeps the class from being unloaded, which would invalidate the method ID.
+    _class: GlobalRef,
+    method_id: JMethodID,
+    sig: TypeSignature,
+}
+
+impl MethodInvoker {
+    /// The return type of the method is derived from its JNI signature `sig`.
+    pub(crate) fn try_new(
+        env: &mut JNIEnv,
+        class_name: &str,
+        method_name: &str,
+        sig: &str,
+    ) -> Result<Self> {
+        let class = class_loader::find_class(env, class_name)?;
+        let sig = classes::resolve_signature(sig);
+        let method_id = env.get_method_id(&class, method_name, &*sig)?;
+        Ok(Self {
+            _class: class,
+            method_id,
+            sig: TypeSignature::from_str(&sig)?,
+        })
+    }
+
+    /// Invokes the method on the `obj` with the `args`.
+    ///
+    /// # Safety
+    ///
+    /// Same as the [StaticMethodInvoker::invoke], and the `obj` must be an instance of the class.
+    pub(crate) unsafe fn invoke<'local, const N: usize>(
+        &self,
+        env: &mut JNIEnv<'local>,
+        obj: &JObject,
+        args: &[JValue; N],
+    ) -> Result<JValueOwned<'local>> {
+        if cfg!(debug_assertions) {
+            check_args(&self.sig, args)?;
+        }
+        let args: [jvalue; N] = args.each_ref().map(|x| x.as_jni());
+        env.call_method_unchecked(obj, self.method_id, self.sig.ret.clone(), &args)
+    }
+}
+
+/// Checks the number of the `args` and their primitive kinds against the signature.
+fn check_args(sig: &TypeSignature, args: &[JValue]) -> Result<()> {
+    let matches = sig.args.len() == args.len()
+        && sig.args.iter().zip(args).all(|(ty, arg)| {
+            matches!(
+                (ty, arg),
+                (JavaType::Object(_) | JavaType::Array(_), JValue::Object(_))
+                    | (JavaType::Primitive(Primitive::Boolean), JValue::Bool(_))
+                    | (JavaType::Primitive(Primitive::Byte), JValue::Byte(_))
+                    | (JavaType::Primitive(Primitive::Char), JValue::Char(_))
+                    | (JavaType::Primitive(Primitive::Short), JValue::Short(_))
+                    | (JavaType::Primitive(Primitive::Int), JValue::Int(_))
+                    | (JavaType::Primitive(Primitive::Long), JValue::Long(_))
+                    | (JavaType::Primitive(Primitive::Float), JValue::Float(_))
+                    | (JavaType::Primitive(Primitive::Double), JValue::Double(_))
+            )
         });
-        unsafe { invoker.invoke($env, $args) }
-    }};
+    if matches {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgList(sig.clone()))
+    }
 }
 
 #[no_mangle]
 pub extern "system" fn JNI_OnLoad(vm: JavaVM, _: *mut c_void) -> jint {
-    let env = &mut unsafe { vm.get_env(JNI_VERSION) }
-        .unwrap_or_else(|e| panic!("Cannot get JNIEnv in 'JNI_OnLoad'? Error: {e:?}"));
+    // Neither an error nor a panic should escape from here: a panic across the FFI boundary
+    // aborts the whole JVM. And they are likely to happen if the jar and the native lib are of
+    // different versions, when some of the classes or methods are missing.
+    let mut env = match unsafe { vm.get_env(JNI_VERSION) } {
+        Ok(env) => env,
+        Err(e) => {
+            error!("Cannot get JNIEnv in 'JNI_OnLoad'? Error: {e:?}");
+            return JNI_ERR;
+        }
+    };
 
-    init(env).unwrap_or_else(|e| {
-        panic!("Failed to initialize JNI_OnLoad: {e}");
-    });
+    let result = panic::catch_unwind(AssertUnwindSafe(|| -> crate::error::Result<()> {
+        let class_name = natives::class_name(&mut env).context(JniSnafu)?;
+        classes::load(&mut env, &class_name)?;
+        natives::register_natives(&mut env).context(JniSnafu)?;
+        init(&mut env).context(JniSnafu)
+    }));
+    let msg = match result {
+        Ok(Ok(())) => return JNI_VERSION.into(),
+        Ok(Err(e)) => format!("Failed to initialize the native lib in 'JNI_OnLoad': {e}"),
+        Err(panic) => {
+            let reason = panic
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic");
+            format!("Panicked initializing the native lib in 'JNI_OnLoad': {reason}")
+        }
+    };
+    throw_link_error(&mut env, &msg);
+    JNI_ERR
+}
+
+/// Throws an `UnsatisfiedLinkError` with the `msg`, caused by the pending exception if any (like
+/// the `NoClassDefFoundError` of a missing class). The `System.loadLibrary` rethrows it.
+fn throw_link_error(env: &mut JNIEnv, msg: &str) {
+    let cause = env.exception_occurred();
+    env.exception_clear();
 
-    JNI_VERSION.into()
+    let result = (|| -> Result<()> {
+        let msg = env.new_string(msg)?;
+        let error = env.new_object(
+            "java/lang/UnsatisfiedLinkError",
+            "(Ljava/lang/String;)V",
+            &[JValue::Object(&msg)],
+        )?;
+        if let Some(cause) = cause {
+            env.call_method(
+                &error,
+                "initCause",
+                "(Ljava/lang/Throwable;)Ljava/lang/Throwable;",
+                &[JValue::Object(&cause)],
+            )?;
+        }
+        env.throw(JThrowable::from(error))
+    })();
+    if let Err(e) = result {
+        error!("Failed to throw UnsatisfiedLinkError in 'JNI_OnLoad': {e:?}");
+        // The JVM fails the loading anyway, by the returned `JNI_ERR`.
+        if env.exception_check() {
+            env.exception_describe();
+            env.exception_clear();
+        }
+    }
 }
 
-fn init(env: &mut JNIEnv) -> Result<()> {
-    LOGGER.get_or_try_init(|| {
-        StaticMethodInvoker::try_new(
-            env,
-            "io/greptime/demo/utils/Logger",
-            "getLogger",
-            "(Ljava/lang/String;)Lio/greptime/demo/utils/Logger;",
-            ReturnType::Object,
-        )
-    })?;
-    ASYNC_REGISTRY_REGISTER.get_or_try_init(|| {
-        StaticMethodInvoker::try_new(
-            env,
-            "io/greptime/demo/utils/AsyncRegistry",
-            "register",
-            "()J",
-            ReturnType::Primitive(Primitive::Long),
-        )
-    })?;
-    ASYNC_REGISTRY_GET_FUTURE.get_or_try_init(|| {
-        StaticMethodInvoker::try_new(
-            env,
-            "io/greptime/demo/utils/AsyncRegistry",
-            "get",
-            "(J)Ljava/util/concurrent/CompletableFuture;",
-            ReturnType::Object,
-        )
-    })?;
+#[no_mangle]
+pub extern "system" fn JNI_OnUnload(vm: JavaVM, _: *mut c_void) {
+    let Ok(mut env) = (unsafe { vm.get_env(JNI_VERSION) }) else {
+        error!("Cannot get JNIEnv in 'JNI_OnUnload'");
+        return;
+    };
+    // Same as in the `JNI_OnLoad`, a panic must not cross the FFI boundary.
+    if panic::catch_unwind(AssertUnwindSafe(|| crate::unload(&mut env))).is_err() {
+        error!("Panicked unloading the native lib in 'JNI_OnUnload'");
+    }
+}
+
+/// Caches all the Java classes, methods and fields that are used in Rust. No-op for the cached
+/// ones.
+pub(crate) fn init(env: &mut JNIEnv) -> Result<()> {
+    bindings::init(env)?;
+    HttpResponse::init(env)?;
     Ok(())
 }
+
+/// Releases the cached members of the lib's own classes, see [bindings::release].
+pub(crate) fn release() {
+    bindings::release();
+    HttpResponse::release();
+}
+
+/// Releases all the cached members, those of the JDK classes included.
+pub(crate) fn release_all() {
+    bindings::release_all();
+    HttpResponse::release();
+}
@@ -12,41 +12,112 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::OnceLock;
-
+use jni::errors::Error;
 use jni::objects::JValue;
-use jni::signature::{Primitive, ReturnType};
-use jni::{InitArgsBuilder, JNIVersion, JavaVM};
-
-use super::StaticMethodInvoker;
-
-fn jvm() -> &'static JavaVM {
-    static JVM: OnceLock<JavaVM> = OnceLock::new();
-    JVM.get_or_init(|| {
-        let jvm_args = InitArgsBuilder::new()
-            .version(JNIVersion::V1_8)
-            .option("-Xcheck:jni")
-            .build()
-            .unwrap();
-        JavaVM::new(jvm_args).unwrap()
-    })
-}
+
+use super::{throw_link_error, MethodInvoker, StaticMethodInvoker};
+use crate::test_util::jvm;
 
 #[test]
 fn test_static_method_invoker() {
     let mut env = jvm().attach_current_thread().unwrap();
 
-    let invoker = StaticMethodInvoker::try_new(
+    let invoker = StaticMethodInvoker::try_new(&mut env, "java/lang/Math", "abs", "(I)I").unwrap();
+
+    let result = unsafe { invoker.invoke(&mut env, &[JValue::Int(-10)]) }
+        .and_then(|x| x.i())
+        .unwrap();
+    assert_eq!(result, 10);
+}
+
+#[test]
+fn test_method_invoker() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let invoker = MethodInvoker::try_new(
         &mut env,
-        "java/lang/Math",
-        "abs",
-        "(I)I",
-        ReturnType::Primitive(Primitive::Int),
+        "java/lang/String",
+        "concat",
+        "(Ljava/lang/String;)Ljava/lang/String;",
     )
     .unwrap();
 
-    let result = unsafe { invoker.invoke(&mut env, &[JValue::Int(-10).as_jni()]) }
+    let hello = env.new_string("hello, ").unwrap();
+    let world = env.new_string("world").unwrap();
+    let result = unsafe { invoker.invoke(&mut env, &hello, &[JValue::Object(&world)]) }
+        .and_then(|x| x.l())
+        .unwrap();
+    let result: String = env.get_string(&result.into()).unwrap().into();
+    assert_eq!(result, "hello, world");
+
+    // The method ID can be used on the instances of the subclasses.
+    let invoker = MethodInvoker::try_new(&mut env, "java/lang/Number", "intValue", "()I").unwrap();
+    let long = env
+        .new_object("java/lang/Long", "(J)V", &[JValue::Long(42)])
+        .unwrap();
+    let result = unsafe { invoker.invoke(&mut env, &long, &[]) }
         .and_then(|x| x.i())
         .unwrap();
-    assert_eq!(result, 10);
+    assert_eq!(result, 42);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_invoke_with_mismatched_args() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let invoker = StaticMethodInvoker::try_new(&mut env, "java/lang/Math", "max", "(JJ)J").unwrap();
+
+    let result = unsafe { invoker.invoke(&mut env, &[JValue::Long(1)]) };
+    assert!(matches!(result, Err(Error::InvalidArgList(_))));
+
+    let result = unsafe { invoker.invoke(&mut env, &[JValue::Long(1), JValue::Int(2)]) };
+    assert!(matches!(result, Err(Error::InvalidArgList(_))));
+
+    let result = unsafe { invoker.invoke(&mut env, &[JValue::Long(1), JValue::Long(2)]) }
+        .and_then(|x| x.j())
+        .unwrap();
+    assert_eq!(result, 2);
+
+    let invoker = MethodInvoker::try_new(
+        &mut env,
+        "java/lang/String",
+        "concat",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+    )
+    .unwrap();
+    let hello = env.new_string("hello").unwrap();
+    let result = unsafe { invoker.invoke(&mut env, &hello, &[JValue::Int(1)]) };
+    assert!(matches!(result, Err(Error::InvalidArgList(_))));
+}
+
+#[test]
+fn test_throw_link_error() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    // Like the missing class in `JNI_OnLoad`.
+    assert!(env.find_class("io/greptime/demo/NoSuchClass").is_err());
+    assert!(env.exception_check());
+
+    throw_link_error(&mut env, "version skew");
+    let error = env.exception_occurred().unwrap();
+    env.exception_clear();
+
+    assert!(env
+        .is_instance_of(&error, "java/lang/UnsatisfiedLinkError")
+        .unwrap());
+    let message = env
+        .call_method(&error, "getMessage", "()Ljava/lang/String;", &[])
+        .and_then(|x| x.l())
+        .unwrap();
+    let message: String = env.get_string(&message.into()).unwrap().into();
+    assert_eq!(message, "version skew");
+
+    let cause = env
+        .call_method(&error, "getCause", "()Ljava/lang/Throwable;", &[])
+        .and_then(|x| x.l())
+        .unwrap();
+    assert!(env
+        .is_instance_of(&cause, "java/lang/NoClassDefFoundError")
+        .unwrap());
 }
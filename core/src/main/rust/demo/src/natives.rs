@@ -0,0 +1,157 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registers the native methods by `RegisterNatives` in the `JNI_OnLoad`, rather than exporting
+//! them by the mangled names like `Java_io_greptime_demo_RustJavaDemo_libInit`, which are bound to
+//! the package of the Java classes. So the classes still work after being relocated, e.g. when
+//! the jar is shaded into another package.
+//!
+//! The Java classes are [resolved](classes::resolve) like all the others, by the names from the
+//! `RustJavaDemo`, whose own name is read from the system property [CLASS_PROPERTY]. The Java side
+//! sets it while loading the lib, and clears it after.
+
+#[cfg(test)]
+mod test;
+
+use std::ffi::c_void;
+
+use jni::errors::Result;
+use jni::objects::JValue;
+use jni::{JNIEnv, NativeMethod};
+
+use crate::{buffer, classes, publisher};
+
+/// The system property of the name of the `RustJavaDemo` class.
+const CLASS_PROPERTY: &str = "greptime.demo.native.class";
+
+/// The name of the `RustJavaDemo` class, if the [CLASS_PROPERTY] is not set.
+const DEFAULT_CLASS: &str = "io/greptime/demo/RustJavaDemo";
+
+/// A native method of a Java class. The classes in the signature are of their original names, and
+/// are [resolved](classes::resolve_signature) when registered.
+struct Native {
+    name: &'static str,
+    sig: &'static str,
+    fn_ptr: *mut c_void,
+}
+
+macro_rules! native {
+    ($name:literal, $sig:literal, $f:expr) => {
+        Native {
+            name: $name,
+            sig: $sig,
+            fn_ptr: $f as *mut c_void,
+        }
+    };
+}
+
+/// The Java classes, by their original names, and their native methods.
+fn natives() -> Vec<(&'static str, Vec<Native>)> {
+    vec![
+        (
+            "io/greptime/demo/RustJavaDemo",
+            vec![
+                native!("libInit", "(Lio/greptime/demo/RuntimeConfig;)V", crate::lib_init),
+                native!(
+                    "registerRuntime",
+                    "(Ljava/lang/String;Lio/greptime/demo/RuntimeConfig;)V",
+                    crate::register_runtime
+                ),
+                native!(
+                    "runtimeMetrics",
+                    "(Ljava/lang/String;)Lio/greptime/demo/RuntimeMetrics;",
+                    crate::runtime_metrics
+                ),
+                native!("libShutdown", "(J)V", crate::lib_shutdown),
+                native!(
+                    "nativeHello",
+                    "(Ljava/util/concurrent/CompletableFuture;Ljava/lang/String;Ljava/lang/String;J)J",
+                    crate::native_hello
+                ),
+                native!(
+                    "nativeHelloBytes",
+                    "(Ljava/util/concurrent/CompletableFuture;Ljava/lang/String;)J",
+                    crate::native_hello_bytes
+                ),
+                native!(
+                    "nativeRange",
+                    "(Ljava/util/concurrent/CompletableFuture;JJJJ)J",
+                    crate::native_range
+                ),
+                native!(
+                    "nativeFetch",
+                    "(Ljava/util/concurrent/CompletableFuture;Ljava/lang/String;)J",
+                    crate::native_fetch
+                ),
+                native!(
+                    "nativeHelloStream",
+                    "(Lio/greptime/demo/utils/RustPublisher;Ljava/lang/String;Ljava/lang/String;)J",
+                    crate::native_hello_stream
+                ),
+                native!(
+                    "nativeEcho",
+                    "(Ljava/util/concurrent/CompletableFuture;Ljava/lang/String;)J",
+                    crate::native_echo
+                ),
+                native!("cancel", "(J)V", crate::cancel),
+            ],
+        ),
+        (
+            "io/greptime/demo/utils/RustPublisher",
+            vec![
+                native!("request", "(JJ)V", publisher::request),
+                native!("cancel", "(J)V", publisher::cancel),
+            ],
+        ),
+        (
+            "io/greptime/demo/NativeBuffer",
+            vec![native!("free", "(J)V", buffer::free)],
+        ),
+    ]
+}
+
+/// Registers all the native methods to the Java classes. The classes must be
+/// [loaded](classes::load) first.
+pub(crate) fn register_natives(env: &mut JNIEnv) -> Result<()> {
+    for (class, natives) in natives() {
+        let methods = natives
+            .iter()
+            .map(|native| NativeMethod {
+                name: native.name.into(),
+                sig: classes::resolve_signature(native.sig).into(),
+                fn_ptr: native.fn_ptr,
+            })
+            .collect::<Vec<_>>();
+        env.register_native_methods(&*classes::resolve(class), &methods)?;
+    }
+    Ok(())
+}
+
+/// Reads the name of the `RustJavaDemo` class from the system property, in the JNI form.
+pub(crate) fn class_name(env: &mut JNIEnv) -> Result<String> {
+    let key = env.new_string(CLASS_PROPERTY)?;
+    let value = env
+        .call_static_method(
+            "java/lang/System",
+            "getProperty",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            &[JValue::Object(&key)],
+        )?
+        .l()?;
+    if value.is_null() {
+        return Ok(DEFAULT_CLASS.to_string());
+    }
+    let value: String = env.get_string(&value.into())?.into();
+    Ok(value.replace('.', "/"))
+}
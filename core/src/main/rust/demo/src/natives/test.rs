@@ -0,0 +1,33 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jni::signature::TypeSignature;
+
+use super::natives;
+
+#[test]
+fn test_natives() {
+    for (class, natives) in natives() {
+        // The original names, which are resolved when registered.
+        assert!(class.starts_with("io/greptime/demo/"), "{}", class);
+        for native in natives {
+            assert!(!native.fn_ptr.is_null());
+            assert!(
+                TypeSignature::from_str(native.sig).is_ok(),
+                "{}",
+                native.sig
+            );
+        }
+    }
+}
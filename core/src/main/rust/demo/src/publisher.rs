@@ -0,0 +1,286 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exposes a Rust [Stream] to Java as a `java.util.concurrent.Flow.Publisher`.
+//!
+//! It works like this:
+//!   1. When the Java `RustPublisher` is subscribed, it calls into Rust to start the stream, and
+//!      gets the id of it.
+//!   2. The Rust side spawns a task to "pump" the stream. The task pulls an item from the stream
+//!      only when the subscriber has requested more, and pushes the item to the `RustPublisher`,
+//!      which in turn signals the subscriber.
+//!   3. The subscriber's `request(n)` and `cancel()` are forwarded to Rust by the stream id.
+//!      Cancelling aborts the task, hence drops the stream.
+
+#[cfg(test)]
+mod test;
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures::{Stream, StreamExt};
+use jni::objects::{GlobalRef, JClass, JObject};
+use jni::sys::jlong;
+use jni::JNIEnv;
+use snafu::ResultExt;
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
+
+use crate::bindings::{RustPublisher, Throwable};
+use crate::error::{Error, JniSnafu, Result, RuntimeShutdownSnafu};
+use crate::logger::error;
+use crate::{jni_env, make_exception, runtime};
+
+/// A running stream, which is pushing items to the `sink`.
+struct PublisherTask {
+    sink: Arc<dyn Sink>,
+    demand: Arc<Demand>,
+    abort: AbortHandle,
+}
+
+/// Where the items of a stream are pushed to, it's the Java `RustPublisher`, or a mock in the
+/// tests.
+trait Sink: Send + Sync + 'static {
+    fn on_next(&self, env: &mut JNIEnv, item: &JObject) -> jni::errors::Result<()>;
+
+    /// Signals the `err`, with the pending Java exception (if any) as its cause.
+    fn on_error(&self, env: &mut JNIEnv, err: &Error) -> jni::errors::Result<()>;
+
+    fn on_complete(&self, env: &mut JNIEnv) -> jni::errors::Result<()>;
+}
+
+/// A Java `RustPublisher`.
+struct JavaPublisher(GlobalRef);
+
+impl Sink for JavaPublisher {
+    fn on_next(&self, env: &mut JNIEnv, item: &JObject) -> jni::errors::Result<()> {
+        // Safety: the `self.0` is a `RustPublisher`, and the item can be any object.
+        unsafe { RustPublisher::onNext(env, &self.0, item) }
+    }
+
+    fn on_error(&self, env: &mut JNIEnv, err: &Error) -> jni::errors::Result<()> {
+        // The exception can't be created while another one is pending, e.g. thrown by the
+        // `onNext`, which is kept as the cause then.
+        let cause = env.exception_occurred().map(|x| env.auto_local(x));
+        env.exception_clear();
+
+        env.with_local_frame(16, |env| {
+            let ex = make_exception(env, err)?;
+            if let Some(cause) = &cause {
+                // Safety: both of them are `Throwable`s.
+                unsafe { Throwable::initCause(env, &ex, cause) }?;
+            }
+            // Safety: the `self.0` is a `RustPublisher`, and the `ex` is a `Throwable`.
+            unsafe { RustPublisher::onError(env, &self.0, &ex) }
+        })
+    }
+
+    fn on_complete(&self, env: &mut JNIEnv) -> jni::errors::Result<()> {
+        // Safety: the `self.0` is a `RustPublisher`.
+        unsafe { RustPublisher::onComplete(env, &self.0) }
+    }
+}
+
+/// Removes the stream from the [STREAMS] when its task ends, even by a panic. A task that is
+/// cancelled is removed by [cancel], and one that is dropped by the shutdown of the runtime is
+/// left to [fail_all].
+struct StreamGuard {
+    stream_id: jlong,
+    ended: bool,
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        if self.ended || std::thread::panicking() {
+            STREAMS.lock().unwrap().remove(&self.stream_id);
+        }
+    }
+}
+
+static NEXT_STREAM_ID: AtomicI64 = AtomicI64::new(1);
+
+/// The running streams, by their ids.
+static STREAMS: Mutex<BTreeMap<jlong, PublisherTask>> = Mutex::new(BTreeMap::new());
+
+/// How many more items the subscriber has requested.
+struct Demand {
+    requested: Mutex<u64>,
+    notify: Notify,
+}
+
+impl Demand {
+    /// According to the reactive streams spec, a demand of `Long.MAX_VALUE` is unbounded.
+    const UNBOUNDED: u64 = i64::MAX as u64;
+
+    fn new() -> Self {
+        Self {
+            requested: Mutex::new(0),
+            notify: Notify::new(),
+        }
+    }
+
+    fn add(&self, n: u64) {
+        {
+            let mut requested = self.requested.lock().unwrap();
+            *requested = requested.saturating_add(n).min(Self::UNBOUNDED);
+        }
+        self.notify.notify_one();
+    }
+
+    /// Waits until there's any demand, and takes one from it.
+    async fn acquire(&self) {
+        loop {
+            {
+                let mut requested = self.requested.lock().unwrap();
+                if *requested == Self::UNBOUNDED {
+                    return;
+                }
+                if *requested > 0 {
+                    *requested -= 1;
+                    return;
+                }
+            }
+            // A permit is stored if `add` is called in between, so no wakeup is lost.
+            self.notify.notified().await;
+        }
+    }
+}
+
+/// Spawns the task that pushes the items of the `stream` to the Java `publisher`, converting them
+/// to Java objects by `into_java`. Returns the id of the stream.
+pub(crate) fn spawn_publisher<S, T, C>(
+    runtime: &str,
+    publisher: GlobalRef,
+    stream: S,
+    into_java: C,
+) -> Result<jlong>
+where
+    S: Stream<Item = Result<T>> + Send + 'static,
+    T: Send + 'static,
+    C: for<'a> Fn(&mut JNIEnv<'a>, T) -> Result<JObject<'a>> + Send + 'static,
+{
+    spawn_pump(
+        runtime,
+        Arc::new(JavaPublisher(publisher)),
+        stream,
+        into_java,
+    )
+}
+
+/// Like [spawn_publisher], but pushes the items to any `sink`.
+fn spawn_pump<S, T, C>(runtime: &str, sink: Arc<dyn Sink>, stream: S, into_java: C) -> Result<jlong>
+where
+    S: Stream<Item = Result<T>> + Send + 'static,
+    T: Send + 'static,
+    C: for<'a> Fn(&mut JNIEnv<'a>, T) -> Result<JObject<'a>> + Send + 'static,
+{
+    let stream_id = NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed);
+    let demand = Arc::new(Demand::new());
+
+    // Hold the lock until the stream is inserted, in case the stream is done before that.
+    let mut streams = STREAMS.lock().unwrap();
+    let task_sink = sink.clone();
+    let task_demand = demand.clone();
+    let handle = runtime::spawn(runtime, async move {
+        // Created in the task rather than captured by it, so it's never dropped (if the spawning
+        // fails) while the `STREAMS` is locked above.
+        let mut guard = StreamGuard {
+            stream_id,
+            ended: false,
+        };
+        let mut stream = std::pin::pin!(stream);
+        loop {
+            // Never pull more items than requested.
+            task_demand.acquire().await;
+            let next = stream.next().await;
+
+            let env = &mut jni_env();
+            match next {
+                Some(Ok(item)) => {
+                    let result = env.with_local_frame(16, |env| -> Result<()> {
+                        let item = into_java(env, item)?;
+                        task_sink.on_next(env, &item).context(JniSnafu)
+                    });
+                    if let Err(e) = result {
+                        on_error(env, &task_sink, &e);
+                        break;
+                    }
+                }
+                Some(Err(e)) => {
+                    on_error(env, &task_sink, &e);
+                    break;
+                }
+                None => {
+                    on_complete(env, &task_sink);
+                    break;
+                }
+            }
+        }
+        guard.ended = true;
+    })?;
+
+    streams.insert(
+        stream_id,
+        PublisherTask {
+            sink,
+            demand,
+            abort: handle.abort_handle(),
+        },
+    );
+    Ok(stream_id)
+}
+
+fn on_error(env: &mut JNIEnv, sink: &dyn Sink, err: &Error) {
+    if let Err(e) = sink.on_error(env, err) {
+        env.exception_clear();
+        error!(
+            "Failed to signal Java publisher with error '{:?}', error: {:?}",
+            err, e
+        );
+    }
+}
+
+fn on_complete(env: &mut JNIEnv, sink: &dyn Sink) {
+    if let Err(e) = sink.on_complete(env) {
+        env.exception_clear();
+        error!(
+            "Failed to signal Java publisher to complete, error: {:?}",
+            e
+        );
+    }
+}
+
+/// Fails all the running streams, after the runtime is shut down.
+pub(crate) fn fail_all(env: &mut JNIEnv) {
+    let streams = std::mem::take(&mut *STREAMS.lock().unwrap());
+    for stream in streams.into_values() {
+        on_error(env, &stream.sink, &RuntimeShutdownSnafu.build());
+    }
+}
+
+pub(crate) extern "system" fn request(_env: JNIEnv, _class: JClass, stream_id: jlong, n: jlong) {
+    if let Some(stream) = STREAMS.lock().unwrap().get(&stream_id) {
+        stream.demand.add(n as u64);
+    }
+}
+
+pub(crate) extern "system" fn cancel(_env: JNIEnv, _class: JClass, stream_id: jlong) {
+    // Aborting the task drops the stream, as well as the resources it holds. It's done out of the
+    // lock, in case the task is dropped in place.
+    let stream = STREAMS.lock().unwrap().remove(&stream_id);
+    if let Some(stream) = stream {
+        stream.abort.abort();
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use futures::{stream, Stream, StreamExt};
+use jni::objects::{JClass, JObject};
+use jni::sys::jlong;
+use jni::JNIEnv;
+
+use super::{cancel, request, spawn_pump, Demand, Sink, STREAMS};
+use crate::convert::IntoJava;
+use crate::error::{Error, Result};
+use crate::runtime::{self, RuntimeConfig};
+use crate::test_util::{jvm, DropGuard};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[tokio::test]
+async fn test_demand_not_exceeded() {
+    let demand = Arc::new(Demand::new());
+    demand.add(2);
+
+    demand.acquire().await;
+    demand.acquire().await;
+    let exhausted = tokio::time::timeout(Duration::from_millis(50), demand.acquire()).await;
+    assert!(exhausted.is_err());
+
+    // A request made while waiting wakes the waiter up.
+    let waiter = tokio::spawn({
+        let demand = demand.clone();
+        async move { demand.acquire().await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    demand.add(1);
+    tokio::time::timeout(Duration::from_secs(1), waiter)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_demand_unbounded() {
+    let demand = Demand::new();
+    demand.add(Demand::UNBOUNDED);
+    // Adding more never overflows.
+    demand.add(Demand::UNBOUNDED);
+
+    for _ in 0..1000 {
+        demand.acquire().await;
+    }
+    assert_eq!(*demand.requested.lock().unwrap(), Demand::UNBOUNDED);
+}
+
+/// A signal that is received by the [MockSink].
+#[derive(Debug)]
+enum Signal {
+    Next,
+    Error { jni: bool, pending: bool },
+    Complete,
+}
+
+/// Sends the signals it receives, and throws in the `on_next` if it `rejects`, like a subscriber
+/// that throws in its `onNext`.
+struct MockSink {
+    signals: mpsc::Sender<Signal>,
+    rejects: bool,
+}
+
+impl Sink for MockSink {
+    fn on_next(&self, env: &mut JNIEnv, _item: &JObject) -> jni::errors::Result<()> {
+        if self.rejects {
+            env.throw_new("java/lang/IllegalStateException", "rejected")?;
+            return Err(jni::errors::Error::JavaException);
+        }
+        let _ = self.signals.send(Signal::Next);
+        Ok(())
+    }
+
+    fn on_error(&self, env: &mut JNIEnv, err: &Error) -> jni::errors::Result<()> {
+        let pending = env.exception_check();
+        env.exception_clear();
+        let jni = matches!(err, Error::Jni { .. });
+        let _ = self.signals.send(Signal::Error { jni, pending });
+        Ok(())
+    }
+
+    fn on_complete(&self, _env: &mut JNIEnv) -> jni::errors::Result<()> {
+        let _ = self.signals.send(Signal::Complete);
+        Ok(())
+    }
+}
+
+fn init_runtime(env: &mut JNIEnv, name: &str) {
+    let config = RuntimeConfig {
+        worker_threads: 1,
+        ..Default::default()
+    };
+    runtime::init(env, jvm(), name, &config).unwrap();
+}
+
+/// The numbers in `0..n`, counting how many of them are `pulled`.
+fn counting(pulled: Arc<AtomicUsize>, n: i64) -> impl Stream<Item = Result<i64>> + Send + 'static {
+    stream::iter(0..n).map(move |x| {
+        pulled.fetch_add(1, Ordering::SeqCst);
+        Ok(x)
+    })
+}
+
+fn spawn(
+    name: &str,
+    sink: MockSink,
+    stream: impl Stream<Item = Result<i64>> + Send + 'static,
+) -> jlong {
+    spawn_pump(name, Arc::new(sink), stream, |env, x| x.into_java(env)).unwrap()
+}
+
+/// Like the Java side calls it.
+fn request_n(env: &JNIEnv, stream_id: jlong, n: jlong) {
+    let env = unsafe { JNIEnv::from_raw(env.get_raw()) }.unwrap();
+    request(env, JClass::from(JObject::null()), stream_id, n);
+}
+
+/// Waits for the stream to be removed, when its task ends.
+fn wait_removed(stream_id: jlong) {
+    let deadline = Instant::now() + TIMEOUT;
+    while STREAMS.lock().unwrap().contains_key(&stream_id) {
+        assert!(
+            Instant::now() < deadline,
+            "stream {stream_id} is not removed"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_pump_bounded_by_demand() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    let name = "test-pump-demand";
+    init_runtime(&mut env, name);
+
+    let (signals, received) = mpsc::channel();
+    let sink = MockSink {
+        signals,
+        rejects: false,
+    };
+    let pulled = Arc::new(AtomicUsize::new(0));
+    let stream_id = spawn(name, sink, counting(pulled.clone(), 10));
+
+    request_n(&env, stream_id, 3);
+    for _ in 0..3 {
+        assert!(matches!(received.recv_timeout(TIMEOUT), Ok(Signal::Next)));
+    }
+    // No more items are pulled until they are requested.
+    assert!(received.recv_timeout(Duration::from_millis(100)).is_err());
+    assert_eq!(pulled.load(Ordering::SeqCst), 3);
+
+    request_n(&env, stream_id, Demand::UNBOUNDED as jlong);
+    for _ in 0..7 {
+        assert!(matches!(received.recv_timeout(TIMEOUT), Ok(Signal::Next)));
+    }
+    assert!(matches!(
+        received.recv_timeout(TIMEOUT),
+        Ok(Signal::Complete)
+    ));
+    assert_eq!(pulled.load(Ordering::SeqCst), 10);
+    wait_removed(stream_id);
+
+    runtime::remove(name);
+}
+
+#[test]
+fn test_pump_on_next_throws() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    let name = "test-pump-throws";
+    init_runtime(&mut env, name);
+
+    let (signals, received) = mpsc::channel();
+    let sink = MockSink {
+        signals,
+        rejects: true,
+    };
+    let pulled = Arc::new(AtomicUsize::new(0));
+    let (dropped, on_dropped) = mpsc::channel();
+    let guard = DropGuard(dropped);
+    let stream = counting(pulled.clone(), 10).map(move |x| {
+        let _guard = &guard;
+        x
+    });
+    let stream_id = spawn(name, sink, stream);
+
+    request_n(&env, stream_id, 5);
+    // Failed by the exception thrown by the `on_next`, which is still pending for the cause.
+    assert!(matches!(
+        received.recv_timeout(TIMEOUT),
+        Ok(Signal::Error {
+            jni: true,
+            pending: true
+        })
+    ));
+    on_dropped.recv_timeout(TIMEOUT).unwrap();
+    wait_removed(stream_id);
+    assert_eq!(pulled.load(Ordering::SeqCst), 1);
+
+    runtime::remove(name);
+}
+
+#[test]
+fn test_pump_cancel() {
+    let mut env = jvm().attach_current_thread().unwrap();
+    let name = "test-pump-cancel";
+    init_runtime(&mut env, name);
+
+    let (signals, received) = mpsc::channel();
+    let sink = MockSink {
+        signals,
+        rejects: false,
+    };
+    let (dropped, on_dropped) = mpsc::channel();
+    let guard = DropGuard(dropped);
+    let stream = stream::pending().map(move |x| {
+        let _guard = &guard;
+        x
+    });
+    let stream_id = spawn(name, sink, stream);
+    request_n(&env, stream_id, 1);
+
+    // Like the Java side calls it when the subscription is cancelled.
+    let cancel_env = unsafe { JNIEnv::from_raw(env.get_raw()) }.unwrap();
+    cancel(cancel_env, JClass::from(JObject::null()), stream_id);
+    assert!(!STREAMS.lock().unwrap().contains_key(&stream_id));
+    // The task is aborted, and the stream is dropped without any signal.
+    on_dropped.recv_timeout(TIMEOUT).unwrap();
+    assert!(received.try_recv().is_err());
+
+    runtime::remove(name);
+}
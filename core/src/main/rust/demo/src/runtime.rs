@@ -0,0 +1,494 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod test;
+
+use std::collections::BTreeMap;
+use std::ffi::{c_char, c_void, CString};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use std::{ptr, thread};
+
+use jni::objects::{GlobalRef, JObject, JValue};
+use jni::{sys, JNIEnv, JavaVM};
+use snafu::{ensure, OptionExt, ResultExt};
+use tokio::runtime::{Handle, Runtime};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_util::task::TaskTracker;
+
+use crate::convert::FromJava;
+use crate::error::{
+    BuildRuntimeSnafu, InvalidRuntimeConfigSnafu, JniSnafu, Result, RuntimeExistsSnafu,
+    RuntimeNotFoundSnafu, ShutdownInRuntimeSnafu,
+};
+use crate::logger::warn;
+use crate::{bindings, ENV, JNI_VERSION};
+
+/// The name of the runtime that is created in `libInit`, and is used when no runtime is specified.
+pub(crate) const DEFAULT_RUNTIME: &str = "default";
+
+/// All the runtimes, by their names. Each of them has its own threads, so the tasks in one
+/// runtime can not starve the others.
+static RUNTIMES: RwLock<BTreeMap<String, TrackedRuntime>> = RwLock::new(BTreeMap::new());
+
+/// The Rust side of `io.greptime.demo.RuntimeConfig`. A zero means using tokio's default value.
+#[derive(Debug, Clone)]
+pub(crate) struct RuntimeConfig {
+    pub(crate) worker_threads: i32,
+    pub(crate) max_blocking_threads: i32,
+    pub(crate) thread_stack_size: i64,
+    pub(crate) thread_keep_alive_millis: i64,
+    /// "greptime-rust-{name}" if not set, see [RuntimeConfig::thread_name_prefix].
+    pub(crate) thread_name_prefix: Option<String>,
+    pub(crate) event_interval: i32,
+    pub(crate) global_queue_interval: i32,
+    pub(crate) current_thread: bool,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            worker_threads: 0,
+            max_blocking_threads: 0,
+            thread_stack_size: 0,
+            thread_keep_alive_millis: 0,
+            thread_name_prefix: None,
+            event_interval: 0,
+            global_queue_interval: 0,
+            current_thread: false,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Reads the config from a Java `RuntimeConfig` object, a `null` yields the default config.
+    pub(crate) fn from_java(env: &mut JNIEnv, config: &JObject) -> Result<Self> {
+        if config.is_null() {
+            return Ok(Self::default());
+        }
+
+        // Safety: the `config` is an instance of the Java `RuntimeConfig`.
+        unsafe {
+            let thread_name_prefix = bindings::RuntimeConfig::threadNamePrefix()
+                .get(env, config)
+                .context(JniSnafu)?;
+            let thread_name_prefix = Option::<String>::from_java(env, &thread_name_prefix)?;
+
+            Ok(Self {
+                worker_threads: bindings::RuntimeConfig::workerThreads()
+                    .get(env, config)
+                    .context(JniSnafu)?,
+                max_blocking_threads: bindings::RuntimeConfig::maxBlockingThreads()
+                    .get(env, config)
+                    .context(JniSnafu)?,
+                thread_stack_size: bindings::RuntimeConfig::threadStackSize()
+                    .get(env, config)
+                    .context(JniSnafu)?,
+                thread_keep_alive_millis: bindings::RuntimeConfig::threadKeepAliveMillis()
+                    .get(env, config)
+                    .context(JniSnafu)?,
+                thread_name_prefix,
+                event_interval: bindings::RuntimeConfig::eventInterval()
+                    .get(env, config)
+                    .context(JniSnafu)?,
+                global_queue_interval: bindings::RuntimeConfig::globalQueueInterval()
+                    .get(env, config)
+                    .context(JniSnafu)?,
+                current_thread: bindings::RuntimeConfig::currentThread()
+                    .get(env, config)
+                    .context(JniSnafu)?,
+            })
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        for (name, value) in [
+            ("workerThreads", self.worker_threads as i64),
+            ("maxBlockingThreads", self.max_blocking_threads as i64),
+            ("threadStackSize", self.thread_stack_size),
+            ("threadKeepAliveMillis", self.thread_keep_alive_millis),
+            ("eventInterval", self.event_interval as i64),
+            ("globalQueueInterval", self.global_queue_interval as i64),
+        ] {
+            ensure!(
+                value >= 0,
+                InvalidRuntimeConfigSnafu {
+                    reason: format!("`{name}` cannot be less than 0"),
+                }
+            );
+        }
+        if let Some(prefix) = &self.thread_name_prefix {
+            ensure!(
+                !prefix.is_empty(),
+                InvalidRuntimeConfigSnafu {
+                    reason: "`threadNamePrefix` cannot be empty",
+                }
+            );
+            // The thread names are passed to the OS and the JVM as C strings.
+            ensure!(
+                !prefix.contains('\0'),
+                InvalidRuntimeConfigSnafu {
+                    reason: "`threadNamePrefix` cannot contain NUL",
+                }
+            );
+        }
+        ensure!(
+            !self.current_thread || self.worker_threads == 0,
+            InvalidRuntimeConfigSnafu {
+                reason: "`workerThreads` cannot be set in the current-thread mode",
+            }
+        );
+        Ok(())
+    }
+
+    /// The prefix of the names of the threads of the runtime of the `name`. It's
+    /// "greptime-rust-{name}" by default, so the threads are easy to tell as the native ones.
+    fn thread_name_prefix(&self, name: &str) -> String {
+        match &self.thread_name_prefix {
+            Some(prefix) => prefix.clone(),
+            None => format!("greptime-rust-{name}"),
+        }
+    }
+
+    fn builder(&self, name: &str) -> tokio::runtime::Builder {
+        let workers = if self.current_thread {
+            0
+        } else if self.worker_threads == 0 {
+            num_cpus::get()
+        } else {
+            self.worker_threads as usize
+        };
+        let mut builder = if self.current_thread {
+            tokio::runtime::Builder::new_current_thread()
+        } else {
+            let mut builder = tokio::runtime::Builder::new_multi_thread();
+            builder.worker_threads(workers);
+            builder
+        };
+
+        if self.max_blocking_threads > 0 {
+            builder.max_blocking_threads(self.max_blocking_threads as usize);
+        }
+        if self.thread_stack_size > 0 {
+            builder.thread_stack_size(self.thread_stack_size as usize);
+        }
+        if self.thread_keep_alive_millis > 0 {
+            builder.thread_keep_alive(Duration::from_millis(self.thread_keep_alive_millis as u64));
+        }
+        if self.event_interval > 0 {
+            builder.event_interval(self.event_interval as u32);
+        }
+        if self.global_queue_interval > 0 {
+            builder.global_queue_interval(self.global_queue_interval as u32);
+        }
+
+        // The names are also used when attaching the threads to the JVM, so that they can be told
+        // apart in the Java thread dumps. Tokio doesn't tell whether a new thread is a worker or a
+        // blocking one, so they are only numbered.
+        let prefix = self.thread_name_prefix(name);
+        let next_id = AtomicUsize::new(0);
+        builder.thread_name_fn(move || {
+            format!("{}-{}", prefix, next_id.fetch_add(1, Ordering::Relaxed))
+        });
+        builder
+    }
+}
+
+/// A tokio runtime, together with the tracker of all the tasks that are spawned in it.
+///
+/// The tracker is what makes the graceful shutdown possible: tokio itself can only drop the
+/// unfinished tasks at their next yield point, it can not wait for them to be done.
+struct TrackedRuntime {
+    handle: Handle,
+    /// The Java `ThreadGroup` that all the threads of the runtime are attached under.
+    group: GlobalRef,
+    driver: Driver,
+    tasks: TaskTracker,
+}
+
+enum Driver {
+    /// The multi-thread runtime is driven by its own worker threads.
+    MultiThread(Runtime),
+    /// The current-thread runtime only runs tasks inside `block_on`, so a dedicated thread is
+    /// blocked on it until being told to stop. The thread hands the runtime back when it stops.
+    CurrentThread {
+        stop: oneshot::Sender<()>,
+        thread: thread::JoinHandle<Runtime>,
+    },
+}
+
+impl Driver {
+    fn stop(self) -> Runtime {
+        match self {
+            Driver::MultiThread(runtime) => runtime,
+            Driver::CurrentThread { stop, thread } => {
+                let _ = stop.send(());
+                thread
+                    .join()
+                    .unwrap_or_else(|e| panic!("The runtime driver thread panicked: {e:?}"))
+            }
+        }
+    }
+}
+
+impl TrackedRuntime {
+    fn try_new(
+        env: &mut JNIEnv,
+        java_vm: &'static JavaVM,
+        name: &str,
+        config: &RuntimeConfig,
+    ) -> Result<Self> {
+        let prefix = config.thread_name_prefix(name);
+        let group = new_thread_group(env, &prefix).context(JniSnafu)?;
+
+        // Every thread of the runtime, the blocking ones included, is attached to the JVM when
+        // it's started, and detached when it's stopped.
+        let thread_group = group.clone();
+        let runtime = config
+            .builder(name)
+            .on_thread_start(move || attach_current_thread(java_vm, &thread_group))
+            .on_thread_stop(move || detach_current_thread(java_vm))
+            .enable_all()
+            .build()
+            .context(BuildRuntimeSnafu)?;
+        let handle = runtime.handle().clone();
+
+        let driver = if config.current_thread {
+            let (stop, stopped) = oneshot::channel();
+            let thread_group = group.clone();
+            let thread = thread::Builder::new()
+                .name(format!("{}-driver", prefix))
+                .spawn(move || {
+                    attach_current_thread(java_vm, &thread_group);
+                    let _ = runtime.block_on(stopped);
+                    detach_current_thread(java_vm);
+                    runtime
+                })
+                .context(BuildRuntimeSnafu)?;
+            Driver::CurrentThread { stop, thread }
+        } else {
+            Driver::MultiThread(runtime)
+        };
+
+        Ok(Self {
+            handle,
+            group,
+            driver,
+            tasks: TaskTracker::new(),
+        })
+    }
+
+    /// Waits until the `deadline` (or for ever if it's `None`) for the in-flight tasks to be done,
+    /// returns whether they are all done in time. No more tasks can be spawned after this.
+    fn drain(&self, deadline: Option<Instant>) -> bool {
+        self.tasks.close();
+        match deadline {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                self.handle
+                    .block_on(tokio::time::timeout(timeout, self.tasks.wait()))
+                    .is_ok()
+            }
+            None => {
+                self.handle.block_on(self.tasks.wait());
+                true
+            }
+        }
+    }
+
+    /// Stops the runtime, waiting until the `deadline` (or for ever if it's `None`) for the
+    /// blocking threads to finish.
+    fn stop(self, deadline: Option<Instant>) {
+        let runtime = self.driver.stop();
+        match deadline {
+            Some(deadline) => {
+                runtime.shutdown_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => drop(runtime),
+        }
+    }
+}
+
+/// Creates a runtime and registers it under the `name`. Fails if the name is already taken.
+pub(crate) fn init(
+    env: &mut JNIEnv,
+    java_vm: &'static JavaVM,
+    name: &str,
+    config: &RuntimeConfig,
+) -> Result<()> {
+    let mut runtimes = RUNTIMES.write().unwrap();
+    ensure!(!runtimes.contains_key(name), RuntimeExistsSnafu { name });
+
+    let runtime = TrackedRuntime::try_new(env, java_vm, name, config)?;
+    runtimes.insert(name.to_string(), runtime);
+    Ok(())
+}
+
+fn new_thread_group(env: &mut JNIEnv, name: &str) -> jni::errors::Result<GlobalRef> {
+    let name = env.new_string(name)?;
+    let group = env.new_object(
+        "java/lang/ThreadGroup",
+        "(Ljava/lang/String;)V",
+        &[JValue::Object(&name)],
+    )?;
+    env.new_global_ref(group)
+}
+
+/// Attaches the current thread to the JVM as a daemon thread, under the thread `group`, and named
+/// the same as the current thread.
+///
+/// The raw JNI function is used because there's no way to pass the `JavaVMAttachArgs` with the
+/// `JavaVM` in the `jni` crate.
+fn attach_current_thread(java_vm: &JavaVM, group: &GlobalRef) {
+    // The JVM names the thread itself if the name can't be passed as a C string.
+    let name = thread::current().name().and_then(|x| CString::new(x).ok());
+    let mut args = sys::JavaVMAttachArgs {
+        version: JNI_VERSION.into(),
+        name: name
+            .as_ref()
+            .map_or(ptr::null_mut(), |x| x.as_ptr() as *mut c_char),
+        group: group.as_obj().as_raw(),
+    };
+
+    let vm = java_vm.get_java_vm_pointer();
+    let mut env = ptr::null_mut();
+    let result = unsafe {
+        ((**vm).v1_4.AttachCurrentThreadAsDaemon)(
+            vm,
+            &mut env,
+            &mut args as *mut sys::JavaVMAttachArgs as *mut c_void,
+        )
+    };
+    if result != sys::JNI_OK {
+        panic!("Failed to attach tokio's threads to JVM, err code: {result}");
+    }
+
+    ENV.with(|cell| *cell.borrow_mut() = Some(env as *mut sys::JNIEnv));
+}
+
+fn detach_current_thread(java_vm: &JavaVM) {
+    // Clear the cached env first, it's dangling once the thread is detached.
+    if ENV.with(|cell| cell.borrow_mut().take()).is_none() {
+        return;
+    }
+    // Detach the thread explicitly, otherwise the JVM keeps a `java.lang.Thread` object
+    // for it, which in turn pins the classloader that loaded us. This matters a lot for the
+    // blocking threads, which come and go with the keep-alive.
+    let vm = java_vm.get_java_vm_pointer();
+    let result = unsafe { ((**vm).v1_1.DetachCurrentThread)(vm) };
+    if result != sys::JNI_OK {
+        warn!("Failed to detach tokio's thread from JVM, err code: {result}");
+    }
+}
+
+/// Removes the runtime of the `name` and stops it, for the tests to clean up their runtimes.
+#[cfg(test)]
+pub(crate) fn remove(name: &str) {
+    let runtime = RUNTIMES.write().unwrap().remove(name);
+    if let Some(runtime) = runtime {
+        runtime.stop(Some(Instant::now() + Duration::from_secs(1)));
+    }
+}
+
+/// Spawns the future in the runtime of the `name`. Fails if the runtime is not running, that is,
+/// it is either not registered yet or has already been shut down.
+pub(crate) fn spawn<F>(name: &str, future: F) -> Result<JoinHandle<F::Output>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let runtimes = RUNTIMES.read().unwrap();
+    let runtime = runtimes.get(name).context(RuntimeNotFoundSnafu { name })?;
+    Ok(runtime.tasks.spawn_on(future, &runtime.handle))
+}
+
+// See the `rustflags` in `.cargo/config.toml`.
+#[cfg(not(tokio_unstable))]
+compile_error!(
+    "The runtime metrics need tokio's unstable APIs, build with `--cfg tokio_unstable`."
+);
+
+/// A snapshot of the [tokio::runtime::RuntimeMetrics] of a runtime.
+#[derive(Debug)]
+pub(crate) struct MetricsSnapshot {
+    pub(crate) workers: usize,
+    pub(crate) alive_tasks: usize,
+    pub(crate) global_queue_depth: usize,
+    pub(crate) worker_local_queue_depths: Vec<usize>,
+    pub(crate) worker_busy_durations: Vec<Duration>,
+    pub(crate) worker_park_counts: Vec<u64>,
+    pub(crate) blocking_threads: usize,
+    pub(crate) idle_blocking_threads: usize,
+    pub(crate) blocking_queue_depth: usize,
+}
+
+/// Takes a snapshot of the metrics of the runtime of the `name`.
+pub(crate) fn metrics(name: &str) -> Result<MetricsSnapshot> {
+    let runtimes = RUNTIMES.read().unwrap();
+    let runtime = runtimes.get(name).context(RuntimeNotFoundSnafu { name })?;
+
+    let metrics = runtime.handle.metrics();
+    let workers = metrics.num_workers();
+    Ok(MetricsSnapshot {
+        workers,
+        alive_tasks: metrics.num_alive_tasks(),
+        global_queue_depth: metrics.global_queue_depth(),
+        worker_local_queue_depths: (0..workers)
+            .map(|i| metrics.worker_local_queue_depth(i))
+            .collect(),
+        worker_busy_durations: (0..workers)
+            .map(|i| metrics.worker_total_busy_duration(i))
+            .collect(),
+        worker_park_counts: (0..workers).map(|i| metrics.worker_park_count(i)).collect(),
+        blocking_threads: metrics.num_blocking_threads(),
+        idle_blocking_threads: metrics.num_idle_blocking_threads(),
+        blocking_queue_depth: metrics.blocking_queue_depth(),
+    })
+}
+
+/// Shuts down all the runtimes: stops accepting new tasks first, then waits up to `timeout` for
+/// the in-flight tasks to be done. Tasks that are still running after that are dropped.
+///
+/// Returns whether all the in-flight tasks are done in time. Fails if it's called from a thread of
+/// a runtime (like a Java callback of a future that is completed by a task), which can't wait
+/// for the runtimes, or stop its own one.
+pub(crate) fn shutdown(timeout: Duration) -> Result<bool> {
+    ensure!(Handle::try_current().is_err(), ShutdownInRuntimeSnafu);
+    let runtimes = std::mem::take(&mut *RUNTIMES.write().unwrap());
+
+    // A timeout that is too long to be a deadline, like `Duration::MAX`, means waiting for ever.
+    let deadline = Instant::now().checked_add(timeout);
+    let mut drained = true;
+    for (name, runtime) in &runtimes {
+        if !runtime.drain(deadline) {
+            warn!(
+                "{} tasks are still running in runtime '{}' after waiting for {:?}, dropping them",
+                runtime.tasks.len(),
+                name,
+                timeout
+            );
+            drained = false;
+        }
+    }
+
+    // Wait for the blocking threads in the remaining time.
+    for (_, runtime) in runtimes {
+        runtime.stop(deadline);
+    }
+    Ok(drained)
+}
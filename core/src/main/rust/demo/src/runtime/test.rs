@@ -0,0 +1,295 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{mpsc, Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use jni::objects::JObject;
+use jni::JNIEnv;
+
+use super::{init, metrics, remove, shutdown, spawn, RuntimeConfig, TrackedRuntime, RUNTIMES};
+use crate::error::Error;
+use crate::jni_env;
+use crate::test_util::jvm;
+
+/// The number of the live threads in the thread group.
+fn live_threads(env: &mut JNIEnv, group: &JObject) -> i32 {
+    env.call_method(group, "activeCount", "()I", &[])
+        .and_then(|x| x.i())
+        .unwrap()
+}
+
+#[test]
+fn test_detach_threads_on_stop() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let config = RuntimeConfig {
+        worker_threads: 1,
+        max_blocking_threads: 16,
+        thread_keep_alive_millis: 100,
+        thread_name_prefix: Some("test-detach".to_string()),
+        ..Default::default()
+    };
+    let runtime = TrackedRuntime::try_new(&mut env, jvm(), "detach", &config).unwrap();
+    let group = runtime.group.clone();
+
+    // The blocking tasks are held until the threads are counted, so each of them must be running
+    // on its own thread by then.
+    let barrier = Arc::new(Barrier::new(17));
+    let tasks = (0..16)
+        .map(|_| {
+            let barrier = barrier.clone();
+            runtime.handle.spawn_blocking(move || {
+                barrier.wait();
+                barrier.wait();
+            })
+        })
+        .collect::<Vec<_>>();
+    barrier.wait();
+    let burst = live_threads(&mut env, &group);
+    barrier.wait();
+    runtime.handle.block_on(async {
+        for task in tasks {
+            task.await.unwrap();
+        }
+    });
+    assert_eq!(burst, config.worker_threads + 16);
+
+    // The idle blocking threads exit after the keep-alive, and should be detached from the JVM.
+    let start = Instant::now();
+    let mut after = burst;
+    while after > config.worker_threads && start.elapsed() < Duration::from_secs(5) {
+        thread::sleep(Duration::from_millis(100));
+        after = live_threads(&mut env, &group);
+    }
+    assert_eq!(after, config.worker_threads);
+
+    runtime.stop(Some(Instant::now() + Duration::from_secs(1)));
+    assert_eq!(live_threads(&mut env, &group), 0);
+}
+
+#[test]
+fn test_thread_names() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    // The threads are named after the runtime by default.
+    let config = RuntimeConfig {
+        worker_threads: 1,
+        ..Default::default()
+    };
+    let runtime = TrackedRuntime::try_new(&mut env, jvm(), "test-names", &config).unwrap();
+    let numbered = |name: &str, prefix: &str| {
+        name.strip_prefix(prefix)
+            .and_then(|x| x.strip_prefix('-'))
+            .is_some_and(|x| x.parse::<usize>().is_ok())
+    };
+
+    let java_thread_name = || {
+        let env = &mut jni_env();
+        let thread = env
+            .call_static_method(
+                "java/lang/Thread",
+                "currentThread",
+                "()Ljava/lang/Thread;",
+                &[],
+            )
+            .and_then(|x| x.l())
+            .unwrap();
+        let name = env
+            .call_method(thread, "getName", "()Ljava/lang/String;", &[])
+            .and_then(|x| x.l())
+            .unwrap();
+        String::from(env.get_string(&name.into()).unwrap())
+    };
+    let worker = runtime
+        .handle
+        .block_on(runtime.handle.spawn(async move { java_thread_name() }))
+        .unwrap();
+    assert!(numbered(&worker, "greptime-rust-test-names"), "{}", worker);
+    let blocking = runtime
+        .handle
+        .block_on(runtime.handle.spawn_blocking(java_thread_name))
+        .unwrap();
+    assert!(
+        numbered(&blocking, "greptime-rust-test-names"),
+        "{}",
+        blocking
+    );
+    assert_ne!(worker, blocking);
+    runtime.stop(Some(Instant::now() + Duration::from_secs(1)));
+
+    let config = RuntimeConfig {
+        worker_threads: 1,
+        thread_name_prefix: Some("custom".to_string()),
+        ..Default::default()
+    };
+    let runtime = TrackedRuntime::try_new(&mut env, jvm(), "test-names", &config).unwrap();
+    let worker = runtime
+        .handle
+        .block_on(runtime.handle.spawn(async move { java_thread_name() }))
+        .unwrap();
+    assert!(numbered(&worker, "custom"), "{}", worker);
+    runtime.stop(Some(Instant::now() + Duration::from_secs(1)));
+}
+
+#[test]
+fn test_validate_config() {
+    assert!(RuntimeConfig::default().validate().is_ok());
+
+    let invalid_configs = [
+        RuntimeConfig {
+            worker_threads: -1,
+            ..Default::default()
+        },
+        RuntimeConfig {
+            max_blocking_threads: -1,
+            ..Default::default()
+        },
+        RuntimeConfig {
+            thread_stack_size: -1,
+            ..Default::default()
+        },
+        RuntimeConfig {
+            thread_keep_alive_millis: -1,
+            ..Default::default()
+        },
+        RuntimeConfig {
+            event_interval: -1,
+            ..Default::default()
+        },
+        RuntimeConfig {
+            global_queue_interval: -1,
+            ..Default::default()
+        },
+        RuntimeConfig {
+            thread_name_prefix: Some(String::new()),
+            ..Default::default()
+        },
+        RuntimeConfig {
+            thread_name_prefix: Some("test\0names".to_string()),
+            ..Default::default()
+        },
+        RuntimeConfig {
+            current_thread: true,
+            worker_threads: 2,
+            ..Default::default()
+        },
+    ];
+    let reasons = [
+        "`workerThreads` cannot be less than 0",
+        "`maxBlockingThreads` cannot be less than 0",
+        "`threadStackSize` cannot be less than 0",
+        "`threadKeepAliveMillis` cannot be less than 0",
+        "`eventInterval` cannot be less than 0",
+        "`globalQueueInterval` cannot be less than 0",
+        "`threadNamePrefix` cannot be empty",
+        "`threadNamePrefix` cannot contain NUL",
+        "`workerThreads` cannot be set in the current-thread mode",
+    ];
+    for (config, reason) in invalid_configs.iter().zip(reasons) {
+        match config.validate() {
+            Err(Error::InvalidRuntimeConfig { reason: x, .. }) => assert_eq!(x, reason),
+            x => panic!("Expected an invalid config error of '{reason}', got: {x:?}"),
+        }
+    }
+
+    let config = RuntimeConfig {
+        current_thread: true,
+        ..Default::default()
+    };
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_runtime_names() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let name = "test-runtime-names";
+    let config = RuntimeConfig {
+        worker_threads: 1,
+        ..Default::default()
+    };
+    init(&mut env, jvm(), name, &config).unwrap();
+    assert!(matches!(
+        init(&mut env, jvm(), name, &config),
+        Err(Error::RuntimeExists { .. })
+    ));
+    let task = spawn(name, async { 42 }).unwrap();
+
+    let missing = "test-runtime-missing";
+    assert!(matches!(
+        spawn(missing, async {}),
+        Err(Error::RuntimeNotFound { .. })
+    ));
+    assert!(matches!(
+        metrics(missing),
+        Err(Error::RuntimeNotFound { .. })
+    ));
+
+    assert_eq!(futures::executor::block_on(task).unwrap(), 42);
+    remove(name);
+}
+
+#[test]
+fn test_stop_without_deadline() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let config = RuntimeConfig {
+        worker_threads: 1,
+        ..Default::default()
+    };
+    let runtime = TrackedRuntime::try_new(&mut env, jvm(), "test-no-deadline", &config).unwrap();
+    let (done, on_done) = mpsc::channel();
+    runtime.tasks.spawn_on(
+        async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let _ = done.send(());
+        },
+        &runtime.handle,
+    );
+
+    // Like the `shutdown` with a timeout that is too long to be a deadline, waits for the task.
+    assert!(Instant::now().checked_add(Duration::MAX).is_none());
+    assert!(runtime.drain(None));
+    on_done.try_recv().unwrap();
+    runtime.stop(None);
+}
+
+#[test]
+fn test_shutdown_in_runtime() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let name = "test-shutdown-in-runtime";
+    let config = RuntimeConfig {
+        worker_threads: 1,
+        ..Default::default()
+    };
+    init(&mut env, jvm(), name, &config).unwrap();
+
+    // Like a Java callback of a future that is completed by a task calls the `libShutdown`.
+    let (result, on_result) = mpsc::channel();
+    spawn(name, async move {
+        let _ = result.send(shutdown(Duration::ZERO));
+    })
+    .unwrap();
+    assert!(matches!(
+        on_result.recv_timeout(Duration::from_secs(5)),
+        Ok(Err(Error::ShutdownInRuntime { .. }))
+    ));
+    // The runtimes are left as is.
+    assert!(RUNTIMES.read().unwrap().contains_key(name));
+
+    remove(name);
+}
@@ -0,0 +1,96 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use jni::objects::{JClass, JObject};
+use jni::JNIEnv;
+use snafu::IntoError;
+
+use crate::error::{
+    FormatSnafu, JniSnafu, NotInitializedSnafu, ReqwestSnafu, RuntimeShutdownSnafu, TimeoutSnafu,
+};
+use crate::runtime::{self, RuntimeConfig};
+use crate::test_util::{jvm, DropGuard};
+use crate::{cancel, exception_class, spawn_task, timeout, TASKS};
+
+#[test]
+fn test_cancel_task() {
+    let mut env = jvm().attach_current_thread().unwrap();
+
+    let name = "test-cancel";
+    let config = RuntimeConfig {
+        worker_threads: 1,
+        ..Default::default()
+    };
+    runtime::init(&mut env, jvm(), name, &config).unwrap();
+
+    let future = env
+        .new_object("java/util/concurrent/CompletableFuture", "()V", &[])
+        .unwrap();
+    let future = env.new_global_ref(future).unwrap();
+    let (dropped, on_dropped) = mpsc::channel();
+    let guard = DropGuard(dropped);
+    let task_id = spawn_task(&mut env, name, future, None, async move {
+        let _guard = guard;
+        std::future::pending::<()>().await
+    });
+    assert!(TASKS.lock().unwrap().contains_key(&task_id));
+
+    // Like the Java side calls it when the future is cancelled.
+    let cancel_env = unsafe { JNIEnv::from_raw(env.get_raw()) }.unwrap();
+    cancel(cancel_env, JClass::from(JObject::null()), task_id);
+    assert!(!TASKS.lock().unwrap().contains_key(&task_id));
+    // The task is aborted, and the future is dropped together with what it holds.
+    on_dropped.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    runtime::remove(name);
+}
+
+#[test]
+fn test_exception_class() {
+    let err = JniSnafu.into_error(jni::errors::Error::NullPtr("test"));
+    assert_eq!(exception_class(&err), "RustJniException");
+
+    // The invalid URL fails the request before it's sent.
+    let error = reqwest::Client::new().get("not a url").build().unwrap_err();
+    let err = ReqwestSnafu.into_error(error);
+    assert_eq!(exception_class(&err), "RustHttpException");
+
+    let timeout = Duration::from_secs(1);
+    let err = TimeoutSnafu { timeout }.build();
+    assert_eq!(exception_class(&err), "RustTimeoutException");
+
+    assert_eq!(
+        exception_class(&RuntimeShutdownSnafu.build()),
+        "RustException"
+    );
+    assert_eq!(
+        exception_class(&NotInitializedSnafu.build()),
+        "RustException"
+    );
+    let err = FormatSnafu.into_error(std::fmt::Error);
+    assert_eq!(exception_class(&err), "RustException");
+}
+
+#[test]
+fn test_timeout() {
+    assert_eq!(timeout(0), None);
+    assert_eq!(timeout(100), Some(Duration::from_millis(100)));
+    // Like a `Long.MAX_VALUE` saturated by the `TimeUnit::toMillis`, it must never overflow a
+    // deadline.
+    let max = timeout(jni::sys::jlong::MAX);
+    assert!(max.is_none_or(|x| Instant::now().checked_add(x).is_some()));
+}
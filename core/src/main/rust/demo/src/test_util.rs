@@ -0,0 +1,39 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{mpsc, OnceLock};
+
+use jni::{InitArgsBuilder, JNIVersion, JavaVM};
+
+/// The JVM shared by all the tests, since there can only be one JVM in a process.
+pub(crate) fn jvm() -> &'static JavaVM {
+    static JVM: OnceLock<JavaVM> = OnceLock::new();
+    JVM.get_or_init(|| {
+        let jvm_args = InitArgsBuilder::new()
+            .version(JNIVersion::V1_8)
+            .option("-Xcheck:jni")
+            .build()
+            .unwrap();
+        JavaVM::new(jvm_args).unwrap()
+    })
+}
+
+/// Tells when it's dropped.
+pub(crate) struct DropGuard(pub(crate) mpsc::Sender<()>);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
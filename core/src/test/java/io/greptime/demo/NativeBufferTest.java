@@ -0,0 +1,117 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package io.greptime.demo;
+
+import static org.junit.Assert.assertArrayEquals;
+import static org.junit.Assert.assertNull;
+import static org.junit.Assert.assertTrue;
+
+import com.sun.net.httpserver.HttpServer;
+import java.io.IOException;
+import java.io.OutputStream;
+import java.lang.ref.WeakReference;
+import java.net.InetAddress;
+import java.net.InetSocketAddress;
+import java.nio.ByteBuffer;
+import org.junit.AfterClass;
+import org.junit.BeforeClass;
+import org.junit.Test;
+
+/**
+ * Tests that the Rust memory behind a {@link NativeBuffer} is freed exactly
+ * once, whether it's closed, closed again, or left to the {@code Cleaner}.
+ */
+public class NativeBufferTest {
+
+    private static final RustJavaDemo DEMO = new RustJavaDemo();
+    private static final byte[] BYTES = new byte[256];
+
+    private static HttpServer server;
+    private static String url;
+
+    @BeforeClass
+    public static void setUp() throws IOException {
+        RustJavaDemo.libInit(1);
+
+        for (int i = 0; i < BYTES.length; i++) {
+            BYTES[i] = (byte) i;
+        }
+        server = HttpServer.create(new InetSocketAddress(InetAddress.getLoopbackAddress(), 0), 0);
+        server.createContext("/bytes", exchange -> {
+            exchange.sendResponseHeaders(200, BYTES.length);
+            try (OutputStream body = exchange.getResponseBody()) {
+                body.write(BYTES);
+            }
+        });
+        server.start();
+        url = "http://127.0.0.1:" + server.getAddress().getPort() + "/bytes";
+    }
+
+    @AfterClass
+    public static void tearDown() {
+        server.stop(0);
+    }
+
+    private static byte[] read(NativeBuffer buffer) {
+        ByteBuffer bytes = buffer.buffer();
+        assertTrue(bytes.isDirect());
+        byte[] result = new byte[bytes.remaining()];
+        bytes.get(result);
+        return result;
+    }
+
+    @Test
+    public void testCloseTwice() {
+        NativeBuffer buffer = DEMO.helloBytes(url).join();
+        assertArrayEquals(BYTES, read(buffer));
+        buffer.close();
+        // Must not free the memory again.
+        buffer.close();
+    }
+
+    @Test
+    public void testCloseThenClean() throws InterruptedException {
+        NativeBuffer buffer = DEMO.helloBytes(url).join();
+        assertArrayEquals(BYTES, read(buffer));
+        buffer.close();
+
+        // The `Cleaner` must not free the memory again once the buffer is unreachable.
+        WeakReference<NativeBuffer> ref = new WeakReference<>(buffer);
+        buffer = null;
+        awaitCollected(ref);
+    }
+
+    @Test
+    public void testCleanWithoutClose() throws InterruptedException {
+        NativeBuffer buffer = DEMO.helloBytes(url).join();
+        assertArrayEquals(BYTES, read(buffer));
+
+        // Freed by the `Cleaner` only.
+        WeakReference<NativeBuffer> ref = new WeakReference<>(buffer);
+        buffer = null;
+        awaitCollected(ref);
+    }
+
+    private static void awaitCollected(WeakReference<?> ref) throws InterruptedException {
+        for (int i = 0; i < 100 && ref.get() != null; i++) {
+            System.gc();
+            Thread.sleep(10);
+        }
+        assertNull("not collected", ref.get());
+        // Gives the `Cleaner` thread a chance to run.
+        System.gc();
+        Thread.sleep(100);
+    }
+}
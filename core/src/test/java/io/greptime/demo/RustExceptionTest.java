@@ -0,0 +1,113 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+package io.greptime.demo;
+
+import static org.junit.Assert.assertEquals;
+import static org.junit.Assert.assertNotNull;
+import static org.junit.Assert.assertThrows;
+import static org.junit.Assert.assertTrue;
+
+import com.sun.net.httpserver.HttpServer;
+import java.io.IOException;
+import java.net.InetAddress;
+import java.net.InetSocketAddress;
+import java.time.Duration;
+import java.util.concurrent.CompletableFuture;
+import java.util.concurrent.CompletionException;
+import org.junit.AfterClass;
+import org.junit.BeforeClass;
+import org.junit.Test;
+
+/**
+ * Tests that the Rust errors are thrown as the exceptions of their kinds, with
+ * the extra data of them.
+ */
+public class RustExceptionTest {
+
+    private static final RustJavaDemo DEMO = new RustJavaDemo();
+
+    private static HttpServer server;
+    private static String baseUrl;
+
+    @BeforeClass
+    public static void setUp() throws IOException {
+        RustJavaDemo.libInit(1);
+
+        server = HttpServer.create(new InetSocketAddress(InetAddress.getLoopbackAddress(), 0), 0);
+        server.createContext("/missing", exchange -> {
+            exchange.sendResponseHeaders(404, -1);
+            exchange.close();
+        });
+        server.createContext("/slow", exchange -> {
+            try {
+                Thread.sleep(1000);
+            } catch (InterruptedException e) {
+                Thread.currentThread().interrupt();
+            }
+            exchange.sendResponseHeaders(200, -1);
+            exchange.close();
+        });
+        server.start();
+        baseUrl = "http://127.0.0.1:" + server.getAddress().getPort();
+    }
+
+    @AfterClass
+    public static void tearDown() {
+        server.stop(0);
+    }
+
+    private static Throwable failure(CompletableFuture<?> future) {
+        return assertThrows(CompletionException.class, future::join).getCause();
+    }
+
+    @Test
+    public void testHttpStatus() {
+        String url = baseUrl + "/missing";
+        CompletableFuture<?>[] futures = {DEMO.hello(url), DEMO.helloBytes(url)};
+        for (CompletableFuture<?> future : futures) {
+            Throwable e = failure(future);
+            assertEquals(RustHttpException.class, e.getClass());
+            RustHttpException http = (RustHttpException) e;
+            assertEquals(404, http.getStatus());
+            assertEquals(url, http.getUrl());
+            assertTrue(http.getLocation(), http.getLocation().contains("lib.rs:"));
+        }
+    }
+
+    @Test
+    public void testTimeout() {
+        Duration timeout = Duration.ofMillis(100);
+        Throwable e = failure(DEMO.hello(baseUrl + "/slow", null, timeout));
+        assertEquals(RustTimeoutException.class, e.getClass());
+        assertEquals(timeout, ((RustTimeoutException) e).getTimeout());
+        assertNotNull(((RustTimeoutException) e).getLocation());
+    }
+
+    @Test
+    public void testNotInitialized() {
+        RustJavaDemo.libShutdown(0);
+        try {
+            RustException e = assertThrows(RustException.class,
+                    () -> RustJavaDemo.registerRuntime("test", null));
+            // Not one of the subclasses.
+            assertEquals(RustException.class, e.getClass());
+            assertTrue(e.getMessage(), e.getMessage().contains("libInit"));
+            assertNotNull(e.getLocation());
+        } finally {
+            RustJavaDemo.libInit(1);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import io.greptime.demo.RustJavaDemo;
+import io.greptime.demo.utils.Logger;
+import java.util.concurrent.CompletableFuture;
+import java.util.concurrent.Semaphore;
+
+/**
+ * Measures the throughput of the async bridge between Java and Rust, with the
+ * tiniest async operation {@link RustJavaDemo#echo(String)}.
+ *
+ * Run it like this:
+ *
+ * <pre>
+ * java -cp ./rust-java-demo-example.jar AsyncBridgeBenchmark [operations] [concurrency]
+ * </pre>
+ */
+public class AsyncBridgeBenchmark {
+
+    static final Logger LOGGER = Logger.getLogger(AsyncBridgeBenchmark.class);
+
+    private static final RustJavaDemo DEMO;
+
+    static {
+        RustJavaDemo.libInit(0);
+
+        DEMO = new RustJavaDemo();
+    }
+
+    public static void main(String[] args) throws Exception {
+        int operations = args.length > 0 ? Integer.parseInt(args[0]) : 1_000_000;
+        int concurrency = args.length > 1 ? Integer.parseInt(args[1]) : 1024;
+
+        // Warm up the JIT and the runtime.
+        run(operations / 10, concurrency);
+
+        long start = System.nanoTime();
+        run(operations, concurrency);
+        long elapsed = System.nanoTime() - start;
+
+        LOGGER.info("{} operations with concurrency {} took {} ms, throughput: {} ops/s",
+                operations, concurrency, elapsed / 1_000_000, operations * 1_000_000_000L / elapsed);
+    }
+
+    private static void run(int operations, int concurrency) throws InterruptedException {
+        Semaphore permits = new Semaphore(concurrency);
+        CompletableFuture<?>[] futures = new CompletableFuture<?>[operations];
+        for (int i = 0; i < operations; i++) {
+            permits.acquire();
+            futures[i] = DEMO.echo("x").whenComplete((r, e) -> permits.release());
+        }
+        CompletableFuture.allOf(futures).join();
+    }
+}